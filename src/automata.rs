@@ -1,12 +1,16 @@
 use std::collections::{BTreeSet, BTreeMap};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 use std::result;
 
+use symbol::Symbol;
+
 pub type State = String;
 pub type StateSet = BTreeSet<State>;
-pub type Delta = BTreeSet<(State, char, State)>;
-pub type DeltaValue = BTreeMap<char, StateSet>;
-pub type DeltaMap = BTreeMap<State, DeltaValue>;
-pub type Alphabet = BTreeSet<char>;
+pub type Delta<S = char> = BTreeSet<(State, S, State)>;
+pub type DeltaValue<S = char> = BTreeMap<S, StateSet>;
+pub type DeltaMap<S = char> = BTreeMap<State, DeltaValue<S>>;
+pub type Alphabet<S = char> = BTreeSet<S>;
 pub type Result = result::Result<(), ()>;
 
 
@@ -15,8 +19,8 @@ pub static TRAP_STATE: &'static str = "trap_state";
 
 //TODO test
 //TODO: rething if the parameter should be an automata or a DeltaMap
-pub fn to_delta(m: &M) -> Delta {
-    let mut delta: Delta = delta!();
+pub fn to_delta<S: Symbol>(m: &M<S>) -> Delta<S> {
+    let mut delta: Delta<S> = BTreeSet::new();
     for (state, delta_value) in &m.delta {
         for (a, next_states) in delta_value {
             for next_state in next_states {
@@ -29,13 +33,13 @@ pub fn to_delta(m: &M) -> Delta {
 }
 
 
-pub fn to_delta_inner(delta_input: Delta) -> DeltaMap {
+pub fn to_delta_inner<S: Symbol>(delta_input: Delta<S>) -> DeltaMap<S> {
     let next_states_blueprint: StateSet = BTreeSet::new();
-    let delta_value_blueprint: DeltaValue = BTreeMap::new();
-    let mut delta: DeltaMap = BTreeMap::new();
+    let delta_value_blueprint: DeltaValue<S> = BTreeMap::new();
+    let mut delta: DeltaMap<S> = BTreeMap::new();
 
-    for &(ref s, a, ref ns) in delta_input.iter() {
-        let mut delta_value: DeltaValue = match delta.get(s) {
+    for (s, a, ns) in delta_input {
+        let mut delta_value: DeltaValue<S> = match delta.get(&s) {
             Some(delta_value) => delta_value.clone(),
             None => delta_value_blueprint.clone(),
         };
@@ -45,16 +49,16 @@ pub fn to_delta_inner(delta_input: Delta) -> DeltaMap {
             None => next_states_blueprint.clone(),
         };
 
-        next_states.insert(ns.clone());
+        next_states.insert(ns);
         delta_value.insert(a, next_states);
-        delta.insert(s.clone(), delta_value);
+        delta.insert(s, delta_value);
     }
 
     delta
 }
 
 
-pub fn print_delta(delta: &DeltaMap) {
+pub fn print_delta<S: Symbol>(delta: &DeltaMap<S>) {
     println!("");
     for (key, value) in delta.iter() {
         println!("{:<20}    {:?}", key, value)
@@ -62,7 +66,7 @@ pub fn print_delta(delta: &DeltaMap) {
     println!("");
 }
 
-pub fn print_automata(m: &M) {
+pub fn print_automata<S: Symbol>(m: &M<S>) {
     println!("AUTOMATA");
     println!("K:  {:?}", m.k);
     println!("q0: {:?}", m.q0);
@@ -74,49 +78,60 @@ pub fn print_automata(m: &M) {
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct M {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "MSchema<S>", try_from = "MSchema<S>"))]
+pub struct M<S: Symbol = char> {
     pub k: StateSet,
-    pub alphabet: BTreeSet<char>,
+    pub alphabet: Alphabet<S>,
     pub q0: State,
     pub f: StateSet,
-    pub delta: DeltaMap,
+    pub delta: DeltaMap<S>,
 
     state: State,
 }
 
-impl M {
-    pub fn new(k: StateSet, alphabet: BTreeSet<char>, q0: State, f: StateSet, delta: Delta) -> M {
-        //TODO: if delta has lambda transitions then dont allow to check string!
+/// JSON schema of M. Delta is a flat list of (state, symbol, next state)
+/// triples, the same shape `M::new` receives, and it is validated
+/// with `M::check` when deserializing.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct MSchema<S: Symbol> {
+    k: StateSet,
+    alphabet: Alphabet<S>,
+    q0: State,
+    f: StateSet,
+    delta: Delta<S>,
+}
 
-        // Check that q0 belongs to K
-        if !k.contains(&q0) {
-            panic!("q0 must belong to K")
+#[cfg(feature = "serde")]
+impl<S: Symbol> From<M<S>> for MSchema<S> {
+    fn from(m: M<S>) -> MSchema<S> {
+        MSchema {
+            delta: to_delta(&m),
+            k: m.k,
+            alphabet: m.alphabet,
+            q0: m.q0,
+            f: m.f,
         }
+    }
+}
 
-        // Check that F is subset of K
-        if !f.is_subset(&k) {
-            panic!("F must be a subset of K")
-        }
+#[cfg(feature = "serde")]
+impl<S: Symbol> TryFrom<MSchema<S>> for M<S> {
+    type Error = String;
 
-        // Check that each element of delta belongs to either K or Alphabet
-        for &(ref current_state, c, ref next_state) in &delta {
-            if !k.contains(current_state) {
-                panic!("Delta is incorrect. In {:?} rule, \"{}\" does not belong to K",
-                       (current_state, c, next_state),
-                       current_state)
-            }
+    fn try_from(schema: MSchema<S>) -> result::Result<M<S>, String> {
+        M::check(&schema.k, &schema.alphabet, &schema.q0, &schema.f, &schema.delta)?;
+        Ok(M::new(schema.k, schema.alphabet, schema.q0, schema.f, schema.delta))
+    }
+}
 
-            if !k.contains(next_state) {
-                panic!("Delta is incorrect. In {:?} rule, \"{}\" does not belong to K",
-                       (current_state, c, next_state),
-                       next_state)
-            }
+impl<S: Symbol> M<S> {
+    pub fn new(k: StateSet, alphabet: Alphabet<S>, q0: State, f: StateSet, delta: Delta<S>) -> M<S> {
+        //TODO: if delta has lambda transitions then dont allow to check string!
 
-            if c != 'λ' && !alphabet.contains(&c) {
-                panic!("Delta is incorrect. In {:?} rule, '{}' does not belong to Alphabet",
-                       (current_state, c, next_state),
-                       c)
-            }
+        if let Err(msg) = M::check(&k, &alphabet, &q0, &f, &delta) {
+            panic!("{}", msg)
         }
 
         let delta = to_delta_inner(delta);
@@ -132,7 +147,50 @@ impl M {
         }
     }
 
-    pub fn next(&mut self, c: char) {
+    /// Checks that the given components define a valid automata
+    /// and describes the first problem found otherwise.
+    pub fn check(k: &StateSet,
+                 alphabet: &Alphabet<S>,
+                 q0: &State,
+                 f: &StateSet,
+                 delta: &Delta<S>)
+                 -> result::Result<(), String> {
+        // Check that q0 belongs to K
+        if !k.contains(q0) {
+            return Err("q0 must belong to K".to_string());
+        }
+
+        // Check that F is subset of K
+        if !f.is_subset(k) {
+            return Err("F must be a subset of K".to_string());
+        }
+
+        // Check that each element of delta belongs to either K or Alphabet
+        for (current_state, c, next_state) in delta {
+            if !k.contains(current_state) {
+                return Err(format!("Delta is incorrect. In {:?} rule, \"{}\" does not belong to K",
+                                   (current_state, c, next_state),
+                                   current_state));
+            }
+
+            if !k.contains(next_state) {
+                return Err(format!("Delta is incorrect. In {:?} rule, \"{}\" does not belong to K",
+                                   (current_state, c, next_state),
+                                   next_state));
+            }
+
+            if !c.is_lambda() && !alphabet.contains(c) {
+                return Err(format!("Delta is incorrect. In {:?} rule, '{}' does not belong to \
+                                    Alphabet",
+                                   (current_state, c, next_state),
+                                   c));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn next(&mut self, c: S) {
         let next_states = self.get_next_states(&self.state, &c);
 
         if next_states.len() > 1 {
@@ -157,17 +215,31 @@ impl M {
         if success { Ok(()) } else { Err(()) }
     }
 
-    pub fn check_string(&mut self, string: &'static str) -> Result {
+    /// Runs the automata over the symbols, the error tells where the
+    /// symbols were rejected.
+    pub fn check_symbols(&mut self, symbols: &[S]) -> result::Result<(), String> {
         self.state = self.q0.clone();
 
-        for c in String::from(string).chars() {
-            self.next(c);
+        for (i, c) in symbols.iter().enumerate() {
+            let defined = self.delta
+                .get(&self.state)
+                .and_then(|delta_value| delta_value.get(c))
+                .is_some_and(|next_states| !next_states.is_empty());
+            if defined {
+                self.next(c.clone());
+                continue;
+            }
+
+            let err = format!("no transition from {:?} on symbol {} {:?}", self.state, i, c);
+            self.state = self.q0.clone();
+            return Err(err);
         }
 
-        self.end()
+        let state = self.state.clone();
+        self.end().map_err(|_| format!("{:?} is not a final state", state))
     }
 
-    pub fn get_next_states(&self, state: &State, a: &char) -> StateSet {
+    pub fn get_next_states(&self, state: &State, a: &S) -> StateSet {
         if let Some(delta_value) = self.delta.get(state) {
             if let Some(next_states) = delta_value.get(a) {
                 return next_states.clone();
@@ -178,10 +250,30 @@ impl M {
     }
 }
 
+impl M<char> {
+    pub fn check_string(&mut self, string: &str) -> Result {
+        let symbols: Vec<char> = string.chars().collect();
+        self.check_symbols(&symbols).map_err(|_| ())
+    }
+}
+
 #[cfg(test)]
 mod tests_automata {
     use super::M;
 
+    #[test]
+    fn check_symbols_error_test() {
+        let k = stateset!("q0", "q1");
+        let delta = delta!(("q0", 'a', "q1"), ("q1", 'b', "q1"));
+        let mut m = M::new(k, alphabet!('a', 'b'), "q0".to_string(), stateset!("q1"), delta);
+
+        assert_eq!(m.check_symbols(&['a', 'b']), Ok(()));
+        assert_eq!(m.check_symbols(&['a', 'a']),
+                   Err("no transition from \"q1\" on symbol 1 'a'".to_string()));
+        assert_eq!(m.check_symbols(&[]), Err("\"q0\" is not a final state".to_string()));
+        assert!(m.check_string("ab").is_ok());
+    }
+
     #[test]
     fn basic_functionality() {
 
@@ -220,6 +312,32 @@ mod tests_automata {
         assert_eq!(ns, stateset!("q1"));
     }
 
+    #[test]
+    fn token_symbols() {
+        use std::collections::BTreeSet;
+
+        let sym = |s: &str| s.to_string();
+        let alphabet: BTreeSet<String> = stateset!("id", "==", "num");
+        let delta: BTreeSet<(String, String, String)> =
+            vec![(sym("q0"), sym("id"), sym("q1")),
+                 (sym("q1"), sym("=="), sym("q2")),
+                 (sym("q2"), sym("num"), sym("q3")),
+                 (sym("q2"), sym("id"), sym("q3"))]
+                .into_iter()
+                .collect();
+
+        let mut automata = M::new(stateset!("q0", "q1", "q2", "q3"),
+                                  alphabet,
+                                  sym("q0"),
+                                  stateset!("q3"),
+                                  delta);
+
+        assert!(automata.check_symbols(&[sym("id"), sym("=="), sym("num")]).is_ok());
+        assert!(automata.check_symbols(&[sym("id"), sym("=="), sym("id")]).is_ok());
+        assert!(automata.check_symbols(&[sym("id"), sym("num")]).is_err());
+        assert!(automata.check_symbols(&[sym("id"), sym("="), sym("num")]).is_err());
+    }
+
     #[test]
     #[should_panic]
     fn panics_q0() {
@@ -0,0 +1,723 @@
+// Questions about the language an automata accepts: enumeration,
+// counting and uniform sampling of its strings, emptiness, universality,
+// finiteness and its shortest and longest strings.
+//
+// The automata is first turned into an Nfa over numbered states and
+// symbols. Emptiness, finiteness and the shortest and longest strings are
+// linear passes over its graph: breadth first searches and the strongly
+// connected components, so they also work on large automatas. Counting and
+// universality need a Dfa, so that lambda transitions or nondeterminism do
+// not count a string more than once; counting is then a dynamic
+// programming over (length, state). States that cannot reach a final state
+// are dropped from the Dfa.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use automata::{M, State};
+use random::Rng;
+use symbol::Symbol;
+
+
+/// Transition on a symbol index, None for lambda, to a state index.
+pub(crate) type Edge = (Option<usize>, usize);
+
+/// Automata over the indexes of `symbols`, a None symbol is a lambda
+/// transition. Transitions to states outside of k are ignored.
+#[derive(Debug, Clone)]
+pub(crate) struct Nfa<S> {
+    pub symbols: Vec<S>,
+    pub initial: usize,
+    pub accepting: Vec<bool>,
+    pub edges: Vec<Vec<Edge>>,
+}
+
+impl<S: Symbol> Nfa<S> {
+    pub fn new(m: &M<S>) -> Nfa<S> {
+        let states: Vec<&State> = m.k.iter().collect();
+        let index: BTreeMap<&State, usize> =
+            states.iter().enumerate().map(|(i, &q)| (q, i)).collect();
+        // Lambda may be listed in the alphabet but it is never read
+        let symbols: Vec<S> = m.alphabet.iter().filter(|a| !a.is_lambda()).cloned().collect();
+
+        let mut edges = vec![vec![]; states.len()];
+        for (q, delta_value) in &m.delta {
+            let from = match index.get(q) {
+                Some(&from) => from,
+                None => continue,
+            };
+            for (a, next_states) in delta_value {
+                let a = if a.is_lambda() {
+                    None
+                } else {
+                    match symbols.binary_search(a) {
+                        Ok(a) => Some(a),
+                        Err(_) => continue,
+                    }
+                };
+                let targets = next_states.iter().filter_map(|n| index.get(n));
+                edges[from].extend(targets.map(|&n| (a, n)));
+            }
+        }
+
+        Nfa {
+            symbols,
+            initial: index[&m.q0],
+            accepting: states.iter().map(|&q| m.f.contains(q)).collect(),
+            edges,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.accepting.len()
+    }
+
+    pub fn closure(&self, set: BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut closure = set;
+        let mut pending: Vec<usize> = closure.iter().cloned().collect();
+        while let Some(q) = pending.pop() {
+            for &(a, n) in &self.edges[q] {
+                if a.is_none() && closure.insert(n) {
+                    pending.push(n);
+                }
+            }
+        }
+
+        closure
+    }
+
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.len()];
+        reachable[self.initial] = true;
+        let mut pending = vec![self.initial];
+        while let Some(q) = pending.pop() {
+            for &(_, n) in &self.edges[q] {
+                if !reachable[n] {
+                    reachable[n] = true;
+                    pending.push(n);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Fewest symbols from each state to a final one, None for dead
+    /// states. Lambda transitions cost nothing, so it is a 0-1 BFS.
+    pub fn distances(&self) -> Vec<Option<usize>> {
+        let mut reverse: Vec<Vec<(usize, usize)>> = vec![vec![]; self.len()];
+        for (q, edges) in self.edges.iter().enumerate() {
+            for &(a, n) in edges {
+                reverse[n].push((a.map_or(0, |_| 1), q));
+            }
+        }
+
+        let mut distances: Vec<Option<usize>> =
+            self.accepting.iter().map(|&a| if a { Some(0) } else { None }).collect();
+        let mut pending: VecDeque<usize> = (0..self.len()).filter(|&q| self.accepting[q]).collect();
+        while let Some(q) = pending.pop_front() {
+            let d = distances[q].unwrap();
+            for &(w, p) in &reverse[q] {
+                if distances[p].is_none_or(|current| d + w < current) {
+                    distances[p] = Some(d + w);
+                    if w == 0 {
+                        pending.push_front(p);
+                    } else {
+                        pending.push_back(p);
+                    }
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Strongly connected component of every state. Tarjan's algorithm
+    /// numbers a component after every component it reaches, so each
+    /// transition goes to the same or a lower number.
+    pub fn components(&self) -> Vec<usize> {
+        let n = self.len();
+        let mut index: Vec<Option<usize>> = vec![None; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = vec![];
+        let mut components = vec![0; n];
+        let mut count = 0;
+        let mut next_index = 0;
+
+        for root in 0..n {
+            if index[root].is_some() {
+                continue;
+            }
+
+            // Explicit call stack of (state, next transition to visit),
+            // recursion would overflow on long chains of states
+            let mut calls = vec![(root, 0)];
+            index[root] = Some(next_index);
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&(q, i)) = calls.last() {
+                if let Some(&(_, p)) = self.edges[q].get(i) {
+                    calls.last_mut().unwrap().1 += 1;
+                    match index[p] {
+                        None => {
+                            index[p] = Some(next_index);
+                            low[p] = next_index;
+                            next_index += 1;
+                            stack.push(p);
+                            on_stack[p] = true;
+                            calls.push((p, 0));
+                        }
+                        Some(p_index) if on_stack[p] => low[q] = low[q].min(p_index),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[q]);
+                }
+                if index[q] == Some(low[q]) {
+                    loop {
+                        let p = stack.pop().unwrap();
+                        on_stack[p] = false;
+                        components[p] = count;
+                        if p == q {
+                            break;
+                        }
+                    }
+                    count += 1;
+                }
+            }
+        }
+
+        components
+    }
+
+    /// States reachable from the initial one that can reach a final one.
+    pub fn useful(&self) -> Vec<bool> {
+        let distances = self.distances();
+        self.reachable()
+            .iter()
+            .zip(distances)
+            .map(|(&reachable, d)| reachable && d.is_some())
+            .collect()
+    }
+}
+
+
+/// Deterministic automata over the indexes of `symbols`, state 0 is the
+/// initial one. Missing transitions lead to an implicit dead state.
+#[derive(Debug, Clone)]
+pub(crate) struct Dfa<S> {
+    pub symbols: Vec<S>,
+    pub accepting: Vec<bool>,
+    pub next: Vec<Vec<Option<usize>>>,
+}
+
+impl<S: Symbol> Dfa<S> {
+    /// Subset construction over the useful part of the automata.
+    pub fn new(m: &M<S>) -> Dfa<S> {
+        let nfa = Nfa::new(m);
+
+        let start = nfa.closure(Some(nfa.initial).into_iter().collect());
+        let mut subsets = vec![start.clone()];
+        let mut subset_index: BTreeMap<BTreeSet<usize>, usize> =
+            Some((start, 0)).into_iter().collect();
+        let mut next: Vec<Vec<Option<usize>>> = vec![];
+
+        let mut i = 0;
+        while i < subsets.len() {
+            let mut row = vec![None; nfa.symbols.len()];
+            for (a, cell) in row.iter_mut().enumerate() {
+                let target = nfa.closure(subsets[i]
+                                             .iter()
+                                             .flat_map(|&q| &nfa.edges[q])
+                                             .filter(|&&(b, _)| b == Some(a))
+                                             .map(|&(_, n)| n)
+                                             .collect());
+                if target.is_empty() {
+                    continue;
+                }
+                let n = subset_index.len();
+                let target_index = *subset_index.entry(target.clone()).or_insert(n);
+                if target_index == n {
+                    subsets.push(target);
+                }
+                *cell = Some(target_index);
+            }
+            next.push(row);
+            i += 1;
+        }
+
+        let accepting = subsets.iter()
+            .map(|subset| subset.iter().any(|&q| nfa.accepting[q]))
+            .collect();
+
+        Dfa {
+            symbols: nfa.symbols,
+            accepting,
+            next,
+        }
+        .trim()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accepting.len()
+    }
+
+    /// Fewest symbols from each state to a final one, None for dead states.
+    pub fn distances(&self) -> Vec<Option<usize>> {
+        let mut reverse: Vec<Vec<usize>> = vec![vec![]; self.len()];
+        for (q, row) in self.next.iter().enumerate() {
+            for &n in row.iter().flatten() {
+                reverse[n].push(q);
+            }
+        }
+
+        let mut distances: Vec<Option<usize>> =
+            self.accepting.iter().map(|&a| if a { Some(0) } else { None }).collect();
+        let mut pending: VecDeque<usize> = (0..self.len()).filter(|&q| self.accepting[q]).collect();
+        while let Some(q) = pending.pop_front() {
+            for &p in &reverse[q] {
+                if distances[p].is_none() {
+                    distances[p] = distances[q].map(|d| d + 1);
+                    pending.push_back(p);
+                }
+            }
+        }
+
+        distances
+    }
+
+    // Redirects the transitions into dead states to the implicit one,
+    // the initial state is kept even when it is dead
+    fn trim(mut self) -> Dfa<S> {
+        let distances = self.distances();
+        for row in &mut self.next {
+            for cell in row.iter_mut() {
+                if cell.is_some_and(|n| distances[n].is_none()) {
+                    *cell = None;
+                }
+            }
+        }
+
+        self
+    }
+
+    /// ways[l][q] is the number of strings of length l accepted from q,
+    /// saturating at u128::MAX.
+    pub fn ways(&self, max_len: usize) -> Vec<Vec<u128>> {
+        let mut ways = vec![self.accepting.iter().map(|&a| a as u128).collect::<Vec<u128>>()];
+        for l in 1..max_len + 1 {
+            let row = self.next
+                .iter()
+                .map(|row| {
+                         row.iter()
+                             .flatten()
+                             .fold(0u128, |acc, &n| acc.saturating_add(ways[l - 1][n]))
+                     })
+                .collect();
+            ways.push(row);
+        }
+
+        ways
+    }
+}
+
+
+impl<S: Symbol> M<S> {
+    /// Accepted strings of at most `max_len` symbols, shorter ones first
+    /// and then in the order of the alphabet.
+    pub fn enumerate(&self, max_len: usize) -> Vec<Vec<S>> {
+        let dfa = Dfa::new(self);
+        let distances = dfa.distances();
+
+        let mut accepted = vec![];
+        let mut level: Vec<(Vec<usize>, usize)> = vec![(vec![], 0)];
+        for len in 0..max_len + 1 {
+            let mut next_level = vec![];
+            for (word, q) in level {
+                if dfa.accepting[q] {
+                    accepted.push(word.iter().map(|&a| dfa.symbols[a].clone()).collect());
+                }
+                for (a, cell) in dfa.next[q].iter().enumerate() {
+                    if let Some(n) = *cell {
+                        // Only prefixes that can still be accepted in time
+                        if distances[n].is_some_and(|d| len + 1 + d <= max_len) {
+                            let mut word = word.clone();
+                            word.push(a);
+                            next_level.push((word, n));
+                        }
+                    }
+                }
+            }
+            level = next_level;
+        }
+
+        accepted
+    }
+
+    /// Number of accepted strings of exactly `len` symbols.
+    pub fn count(&self, len: usize) -> u128 {
+        self.count_up_to(len)[len]
+    }
+
+    /// Number of accepted strings of each length from 0 to `max_len`,
+    /// the counts saturate at u128::MAX.
+    pub fn count_up_to(&self, max_len: usize) -> Vec<u128> {
+        let ways = Dfa::new(self).ways(max_len);
+        ways.iter().map(|row| row[0]).collect()
+    }
+
+    /// Accepted string of `len` symbols chosen uniformly at random,
+    /// None when there is none.
+    pub fn sample(&self, rng: &mut Rng, len: usize) -> Option<Vec<S>> {
+        let dfa = Dfa::new(self);
+        let ways = dfa.ways(len);
+        if ways[len][0] == 0 {
+            return None;
+        }
+
+        let mut word = vec![];
+        let mut q = 0;
+        for l in (0..len).rev() {
+            // Next symbol with probability proportional to the
+            // accepted strings that continue with it
+            let mut x = rng.below_u128(ways[l + 1][q]);
+            for (a, cell) in dfa.next[q].iter().enumerate() {
+                let n = match *cell {
+                    Some(n) => n,
+                    None => continue,
+                };
+                if x < ways[l][n] {
+                    word.push(dfa.symbols[a].clone());
+                    q = n;
+                    break;
+                }
+                x -= ways[l][n];
+            }
+        }
+
+        Some(word)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let nfa = Nfa::new(self);
+        !nfa.useful()[nfa.initial]
+    }
+
+    /// Whether every string over the alphabet is accepted.
+    pub fn is_universal(&self) -> bool {
+        // A missing transition of the trimmed Dfa leads to the dead state
+        let dfa = Dfa::new(self);
+        dfa.accepting.iter().all(|&a| a) && dfa.next.iter().flatten().all(|n| n.is_some())
+    }
+
+    /// Whether the language is finite, that is, no useful state is on a
+    /// cycle that reads a symbol.
+    pub fn is_finite(&self) -> bool {
+        let nfa = Nfa::new(self);
+        let useful = nfa.useful();
+        let components = nfa.components();
+
+        (0..nfa.len()).filter(|&q| useful[q]).all(|q| {
+            nfa.edges[q]
+                .iter()
+                .all(|&(a, n)| a.is_none() || !useful[n] || components[n] != components[q])
+        })
+    }
+
+    /// First accepted string in shortlex order, None when the language
+    /// is empty.
+    pub fn shortest_accepted(&self) -> Option<Vec<S>> {
+        let nfa = Nfa::new(self);
+        let distances = nfa.distances();
+        let mut remaining = distances[nfa.initial]?;
+
+        // States read by the prefix so far that are still at the
+        // shortest distance from a final state
+        let at = |set: BTreeSet<usize>, d: usize| -> BTreeSet<usize> {
+            nfa.closure(set).into_iter().filter(|&q| distances[q] == Some(d)).collect()
+        };
+        let mut current = at(Some(nfa.initial).into_iter().collect(), remaining);
+        let mut word = vec![];
+        while remaining > 0 {
+            remaining -= 1;
+            for a in 0..nfa.symbols.len() {
+                let next = at(current.iter()
+                                  .flat_map(|&q| &nfa.edges[q])
+                                  .filter(|&&(b, _)| b == Some(a))
+                                  .map(|&(_, n)| n)
+                                  .collect(),
+                              remaining);
+                if !next.is_empty() {
+                    word.push(nfa.symbols[a].clone());
+                    current = next;
+                    break;
+                }
+            }
+        }
+
+        Some(word)
+    }
+
+    /// A longest accepted string, None when the language is empty or
+    /// infinite.
+    pub fn longest_accepted(&self) -> Option<Vec<S>> {
+        if !self.is_finite() {
+            return None;
+        }
+
+        let nfa = Nfa::new(self);
+        let useful = nfa.useful();
+        if !useful[nfa.initial] {
+            return None;
+        }
+
+        // The cycles left only have lambda transitions, so every state of
+        // a component reaches the others without reading anything. Longest
+        // string from each component and the transition that starts it,
+        // None when it is better to stop at a final state there.
+        let components = nfa.components();
+        let count = components.iter().max().map_or(0, |&c| c + 1);
+        let mut longest: Vec<Option<(usize, Option<Edge>)>> = vec![None; count];
+        let mut states: Vec<Vec<usize>> = vec![vec![]; count];
+        for q in (0..nfa.len()).filter(|&q| useful[q]) {
+            states[components[q]].push(q);
+        }
+
+        for c in 0..count {
+            for &q in &states[c] {
+                if nfa.accepting[q] && longest[c].is_none() {
+                    longest[c] = Some((0, None));
+                }
+                for &(a, n) in &nfa.edges[q] {
+                    if !useful[n] || components[n] == c {
+                        continue;
+                    }
+                    let length = longest[components[n]].unwrap().0 + a.map_or(0, |_| 1);
+                    if longest[c].is_none_or(|(best, _)| length > best) {
+                        longest[c] = Some((length, Some((a, n))));
+                    }
+                }
+            }
+        }
+
+        let mut word = vec![];
+        let mut c = components[nfa.initial];
+        while let Some((a, n)) = longest[c].unwrap().1 {
+            word.extend(a.map(|a| nfa.symbols[a].clone()));
+            c = components[n];
+        }
+
+        Some(word)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use automata::M;
+    use random::Rng;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    // Strings over {a, b} with an even number of a
+    fn even_a() -> M {
+        let k = stateset!("q0", "q1");
+        let alphabet = alphabet!('a', 'b');
+        let q0 = "q0".to_string();
+        let f = stateset!("q0");
+        let delta = delta!(("q0", 'a', "q1"),
+                           ("q0", 'b', "q0"),
+                           ("q1", 'a', "q0"),
+                           ("q1", 'b', "q1"));
+
+        M::new(k, alphabet, q0, f, delta)
+    }
+
+    // (ab)* | a with lambda transitions and a dead state
+    fn lambda_nfa() -> M {
+        let k = stateset!("q0", "q1", "q2", "q3", "q4", "dead");
+        let alphabet = alphabet!('a', 'b');
+        let q0 = "q0".to_string();
+        let f = stateset!("q1", "q4");
+        let delta = delta!(("q0", 'λ', "q1"),
+                           ("q0", 'λ', "q3"),
+                           ("q1", 'a', "q2"),
+                           ("q2", 'b', "q1"),
+                           ("q3", 'a', "q4"),
+                           ("q3", 'b', "dead"),
+                           ("dead", 'a', "dead"));
+
+        M::new(k, alphabet, q0, f, delta)
+    }
+
+    fn strings(words: Vec<Vec<char>>) -> Vec<String> {
+        words.into_iter().map(|w| w.into_iter().collect()).collect()
+    }
+
+    #[test]
+    fn enumerate_test() {
+        assert_eq!(strings(even_a().enumerate(2)), vec!["", "b", "aa", "bb"]);
+        assert_eq!(strings(lambda_nfa().enumerate(4)), vec!["", "a", "ab", "abab"]);
+        assert_eq!(even_a().enumerate(0), vec![vec![]]);
+    }
+
+    #[test]
+    fn count_test() {
+        // Half of the 2^n strings have an even number of a
+        assert_eq!(even_a().count_up_to(4), vec![1, 1, 2, 4, 8]);
+        assert_eq!(even_a().count(64), 1 << 63);
+        assert_eq!(lambda_nfa().count_up_to(5), vec![1, 1, 1, 0, 1, 0]);
+
+        // The count of each length matches the enumeration
+        let m = lambda_nfa();
+        let words = m.enumerate(8);
+        for len in 0..9 {
+            assert_eq!(m.count(len), words.iter().filter(|w| w.len() == len).count() as u128);
+        }
+    }
+
+    #[test]
+    fn sample_test() {
+        let mut m = even_a();
+        let mut rng = Rng::new(11);
+        let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+        for _ in 0..800 {
+            let word = m.sample(&mut rng, 3).unwrap();
+            assert!(m.check_symbols(&word).is_ok());
+            *seen.entry(word.into_iter().collect()).or_insert(0) += 1;
+        }
+
+        // 4 strings of length 3, each about 200 times
+        assert_eq!(seen.len(), 4);
+        assert!(seen.values().all(|&n| n > 150 && n < 250), "{:?}", seen);
+
+        assert_eq!(lambda_nfa().sample(&mut rng, 3), None);
+        assert_eq!(lambda_nfa().sample(&mut rng, 4), Some(vec!['a', 'b', 'a', 'b']));
+    }
+
+    // {a, ab} with a lambda cycle on useful states and a loop on a dead one
+    fn finite_nfa() -> M {
+        let k = stateset!("q0", "q1", "q2", "q3", "dead");
+        let alphabet = alphabet!('a', 'b');
+        let q0 = "q0".to_string();
+        let f = stateset!("q2", "q3");
+        let delta = delta!(("q0", 'λ', "q1"),
+                           ("q1", 'λ', "q0"),
+                           ("q1", 'a', "q2"),
+                           ("q2", 'b', "q3"),
+                           ("q3", 'a', "dead"),
+                           ("dead", 'b', "dead"));
+
+        M::new(k, alphabet, q0, f, delta)
+    }
+
+    #[test]
+    fn is_empty_test() {
+        assert!(!even_a().is_empty());
+        assert!(!finite_nfa().is_empty());
+
+        // The final state is not reachable
+        let k = stateset!("q0", "q1");
+        let delta = delta!(("q1", 'a', "q0"));
+        let m = M::new(k, alphabet!('a'), "q0".to_string(), stateset!("q1"), delta);
+        assert!(m.is_empty());
+        assert_eq!(m.shortest_accepted(), None);
+        assert_eq!(m.longest_accepted(), None);
+        assert!(m.is_finite());
+    }
+
+    #[test]
+    fn is_universal_test() {
+        assert!(!even_a().is_universal());
+        assert!(!finite_nfa().is_universal());
+
+        // (a | b)* with lambda transitions
+        let k = stateset!("q0", "q1", "q2");
+        let delta = delta!(("q0", 'λ', "q1"),
+                           ("q1", 'a', "q2"),
+                           ("q1", 'b', "q2"),
+                           ("q2", 'λ', "q0"));
+        let m = M::new(k, alphabet!('a', 'b'), "q0".to_string(), stateset!("q0", "q2"), delta);
+        assert!(m.is_universal());
+        assert!(!m.is_finite());
+
+        // a* with lambda listed in the alphabet
+        let delta = delta!(("q0", 'a', "q0"));
+        let alphabet = alphabet!('a', 'λ');
+        let m = M::new(stateset!("q0"), alphabet, "q0".to_string(), stateset!("q0"), delta);
+        assert!(m.is_universal());
+        assert_eq!(m.count_up_to(2), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn is_finite_test() {
+        assert!(!even_a().is_finite());
+        assert!(!lambda_nfa().is_finite());
+        assert!(finite_nfa().is_finite());
+    }
+
+    #[test]
+    fn shortest_and_longest_test() {
+        assert_eq!(even_a().shortest_accepted(), Some(vec![]));
+        assert_eq!(even_a().longest_accepted(), None);
+        assert_eq!(finite_nfa().shortest_accepted(), Some(vec!['a']));
+        assert_eq!(finite_nfa().longest_accepted(), Some(vec!['a', 'b']));
+
+        // {bba, ab, bb}, the shortest in shortlex order is ab
+        let k = stateset!("q0", "q1", "q2", "q3", "q4", "f");
+        let delta = delta!(("q0", 'b', "q1"),
+                           ("q1", 'b', "q2"),
+                           ("q2", 'a', "f"),
+                           ("q0", 'a', "q3"),
+                           ("q3", 'b', "f"),
+                           ("q0", 'b', "q4"),
+                           ("q4", 'b', "f"));
+        let m = M::new(k, alphabet!('a', 'b'), "q0".to_string(), stateset!("f"), delta);
+        assert_eq!(m.shortest_accepted(), Some(vec!['a', 'b']));
+        assert_eq!(m.longest_accepted(), Some(vec!['b', 'b', 'a']));
+    }
+
+    #[test]
+    fn large_automata_test() {
+        // a^n through a chain of n states, and a loop back to q0 from
+        // its last state
+        let n = 20000;
+        let k: BTreeSet<String> = (0..n + 1).map(|i| format!("q{}", i)).collect();
+        let f = stateset!(format!("q{}", n));
+        let mut delta: BTreeSet<(String, char, String)> =
+            (0..n).map(|i| (format!("q{}", i), 'a', format!("q{}", i + 1))).collect();
+        let m = M::new(k.clone(), alphabet!('a'), "q0".to_string(), f.clone(), delta.clone());
+
+        assert!(!m.is_empty());
+        assert!(m.is_finite());
+        assert_eq!(m.shortest_accepted().map(|w| w.len()), Some(n));
+        assert_eq!(m.longest_accepted().map(|w| w.len()), Some(n));
+
+        delta.insert((format!("q{}", n), 'a', "q0".to_string()));
+        let m = M::new(k, alphabet!('a'), "q0".to_string(), f, delta);
+        assert!(!m.is_finite());
+        assert_eq!(m.longest_accepted(), None);
+    }
+
+    #[test]
+    fn token_automata_test() {
+        let k = stateset!("q0", "q1");
+        let alphabet: BTreeSet<String> =
+            vec!["if".to_string(), "x".to_string()].into_iter().collect();
+        let delta = vec![("q0".to_string(), "if".to_string(), "q1".to_string()),
+                         ("q1".to_string(), "x".to_string(), "q1".to_string())]
+            .into_iter()
+            .collect();
+        let m = M::new(k, alphabet, "q0".to_string(), stateset!("q1"), delta);
+
+        assert_eq!(m.enumerate(2),
+                   vec![vec!["if".to_string()], vec!["if".to_string(), "x".to_string()]]);
+        assert_eq!(m.count(10), 1);
+    }
+}
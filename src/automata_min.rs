@@ -1,12 +1,14 @@
 use std::collections::BTreeSet;
-use automata::{M, StateSet, State, Delta, to_delta};
+use automata::{M, StateSet, State, Delta, Alphabet, to_delta, TRAP_STATE};
+use automata_language::Nfa;
 use automata_operators::stateset_name;
+use symbol::Symbol;
 
 pub type RelationMatrixRow = Vec<bool>;
 pub type RelationMatrix = Vec<RelationMatrixRow>;
 
 
-fn get_relation_matrix(m: &M) -> RelationMatrix {
+fn get_relation_matrix<S: Symbol>(m: &M<S>) -> RelationMatrix {
     let mut matrix: RelationMatrix = Vec::new();
 
     for qi in m.k.iter() {
@@ -14,8 +16,8 @@ fn get_relation_matrix(m: &M) -> RelationMatrix {
         for qj in m.k.iter() {
             let mut i_relation_j = false;
 
-            for a in m.alphabet.union(&alphabet!('λ')) {
-                if m.get_next_states(qi, &a).contains(qj) {
+            for a in m.alphabet.iter().chain(Some(&S::lambda())) {
+                if m.get_next_states(qi, a).contains(qj) {
                     i_relation_j = true
                 }
             }
@@ -44,7 +46,7 @@ fn warshall(matrix: &RelationMatrix) -> RelationMatrix {
     r
 }
 
-fn get_reachable_states(m: &M, r: &RelationMatrix) -> StateSet {
+fn get_reachable_states<S: Symbol>(m: &M<S>, r: &RelationMatrix) -> StateSet {
     let states: Vec<State> = m.k.iter().cloned().collect();
     let q0_index = m.k.iter().position(|s| *s == m.q0).unwrap();
     let ref reachable_state_row = r[q0_index];
@@ -63,8 +65,8 @@ fn get_reachable_states(m: &M, r: &RelationMatrix) -> StateSet {
 }
 
 
-fn remove_unreachable_states_with_params(m: &M, reachable_states: StateSet) -> M {
-    let mut m: M = (*m).clone();
+fn remove_unreachable_states_with_params<S: Symbol>(m: &M<S>, reachable_states: StateSet) -> M<S> {
+    let mut m: M<S> = (*m).clone();
     for u in m.k.difference(&reachable_states) {
         let _ = m.delta.remove(u);
     }
@@ -88,7 +90,7 @@ fn get_equivalence_class(states: &StateSet, quotient: &Quotient) -> EquivalenceC
     stateset!()
 }
 
-fn get_quotient(m: &M) -> Quotient {
+fn get_quotient<S: Symbol>(m: &M<S>) -> Quotient {
     let k_f: EquivalenceClass = m.k.difference(&m.f).cloned().collect();
     let f: EquivalenceClass = m.f.clone();
 
@@ -154,7 +156,7 @@ fn get_quotient(m: &M) -> Quotient {
     quotient
 }
 
-fn apply_quotient(m: &M, quotient: &Quotient) -> M {
+fn apply_quotient<S: Symbol>(m: &M<S>, quotient: &Quotient) -> M<S> {
     let states: StateSet = quotient
         .iter()
         .map(|eq_class| stateset_name(eq_class))
@@ -167,7 +169,7 @@ fn apply_quotient(m: &M, quotient: &Quotient) -> M {
         f.insert(final_state);
     }
 
-    let mut delta = delta!();
+    let mut delta: Delta<S> = BTreeSet::new();
     //TODO: Use to_delta
     for (state, delta_value) in &m.delta {
         for (a, next_states) in delta_value {
@@ -184,7 +186,7 @@ fn apply_quotient(m: &M, quotient: &Quotient) -> M {
 
 
 // TODO: conditional compilation on the prints
-fn remove_unreachable_states(m: &M) -> M {
+fn remove_unreachable_states<S: Symbol>(m: &M<S>) -> M<S> {
     {
         use automata::print_automata;
         //println!("Removing unreachable states: received automata");
@@ -205,26 +207,89 @@ fn remove_unreachable_states(m: &M) -> M {
         //println!("reachable states");
         //println!("{:?}", reachable_states);
     }
-    let m: M = remove_unreachable_states_with_params(&m, reachable_states);
+    let m: M<S> = remove_unreachable_states_with_params(&m, reachable_states);
 
     m
 }
 
+/// Removes the states that are unreachable from q0 and the dead ones, from
+/// which no final state can be reached, with their transitions. q0 is
+/// kept even when the language is empty.
+pub fn trim<S: Symbol>(m: &M<S>) -> M<S> {
+    // Nfa numbers the states in the order of k
+    let useful = Nfa::new(m).useful();
+    let k: StateSet = m.k
+        .iter()
+        .zip(useful)
+        .filter(|&(q, useful)| useful || *q == m.q0)
+        .map(|(q, _)| q.clone())
+        .collect();
+
+    let f: StateSet = m.f.intersection(&k).cloned().collect();
+    let delta: Delta<S> = to_delta(m)
+        .into_iter()
+        .filter(|(s, _, ns)| k.contains(s) && k.contains(ns))
+        .collect();
+
+    M::new(k, m.alphabet.clone(), m.q0.clone(), f, delta)
+}
+
+/// Adds the transitions missing on the symbols of the automata and of
+/// `alphabet` to an explicit sink state, named after TRAP_STATE. The
+/// automata is returned as is when nothing is missing. No λ transitions
+/// are added, those of the automata are kept along with λ in its alphabet.
+pub fn complete<S: Symbol>(m: &M<S>, alphabet: &Alphabet<S>) -> M<S> {
+    let symbols: Alphabet<S> =
+        m.alphabet.union(alphabet).filter(|a| !a.is_lambda()).cloned().collect();
+
+    let mut sink: State = TRAP_STATE.to_string();
+    while m.k.contains(&sink) {
+        sink.push('\'');
+    }
+
+    let mut k: StateSet = m.k.clone();
+    let mut delta: Delta<S> = to_delta(m);
+    for q in &m.k {
+        for a in &symbols {
+            let defined = m.delta
+                .get(q)
+                .and_then(|delta_value| delta_value.get(a))
+                .is_some_and(|next_states| !next_states.is_empty());
+            if !defined {
+                delta.insert((q.clone(), a.clone(), sink.clone()));
+                k.insert(sink.clone());
+            }
+        }
+    }
+    if k.contains(&sink) {
+        for a in &symbols {
+            delta.insert((sink.clone(), a.clone(), sink.clone()));
+        }
+    }
+
+    let alphabet: Alphabet<S> = m.alphabet.union(&symbols).cloned().collect();
+    M::new(k, alphabet, m.q0.clone(), m.f.clone(), delta)
+}
+
 // TODO: conditional compilation on the prints
-pub fn minify(m: &M) -> M {
+/// Minimal deterministic automata of a deterministic one. A missing
+/// transition and one to a dead state are told apart, so the result is
+/// only canonical, up to the names of the states, for the output of
+/// `trim`, without a sink, or of `complete`, with one.
+pub fn minify<S: Symbol>(m: &M<S>) -> M<S> {
     {
         //use automata::print_automata;
         //println!("MINIFICATION: received automata");
         //print_automata(&m);
     }
-    let m: M = remove_unreachable_states(m);
+    let m: M<S> = remove_unreachable_states(m);
     {
         //use automata::print_automata;
         //println!("MINIFICATION: automata without unreachable states");
         //print_automata(&m);
     }
     let quotient: Quotient = get_quotient(&m);
-    let m: M = apply_quotient(&m, &quotient);
+    let m: M<S> = apply_quotient(&m, &quotient);
     {
         //use automata::print_automata;
         //println!("MINIFICATION: min automata");
@@ -235,7 +300,7 @@ pub fn minify(m: &M) -> M {
 }
 
 use std::collections::BTreeMap;
-pub fn pretify_automata(m: &M) -> M {
+pub fn pretify_automata<S: Symbol>(m: &M<S>) -> M<S> {
     let prefix: String = "Q".to_string();
     let mut index = 0;
     let mut rename_map: BTreeMap<State, State> = BTreeMap::new();
@@ -270,9 +335,9 @@ pub fn pretify_automata(m: &M) -> M {
         .map(|ref s| rename_map.get(*s).unwrap().clone())
         .collect();
 
-    let delta: Delta = to_delta(&m)
+    let delta: Delta<S> = to_delta(&m)
         .iter()
-        .map(|&(ref s, a, ref ns)| {
+        .map(|(s, a, ns)| {
                  (rename_map.get(s).unwrap().clone(),
                   a.clone(),
                   rename_map.get(ns).unwrap().clone())
@@ -538,6 +603,111 @@ mod tests {
         assert_eq!(min_m, m);
     }
 
+    #[test]
+    fn trim_test() {
+        use super::trim;
+        use automata::M;
+
+        // q3 is unreachable and q2 is dead
+        let k = stateset!("q0", "q1", "q2", "q3");
+        let alphabet = alphabet!('a', 'b');
+        let q0 = "q0".to_string();
+        let f = stateset!("q1", "q3");
+        let delta = delta!(("q0", 'a', "q1"),
+                           ("q0", 'b', "q2"),
+                           ("q1", 'a', "q1"),
+                           ("q1", 'b', "q2"),
+                           ("q2", 'a', "q2"),
+                           ("q2", 'b', "q2"),
+                           ("q3", 'a', "q1"));
+
+        let m = M::new(k, alphabet.clone(), q0.clone(), f, delta);
+        let m_expected = M::new(stateset!("q0", "q1"),
+                                alphabet.clone(),
+                                q0.clone(),
+                                stateset!("q1"),
+                                delta!(("q0", 'a', "q1"), ("q1", 'a', "q1")));
+
+        assert_eq!(trim(&m), m_expected);
+
+        // Only q0 is left of an empty language
+        let m = M::new(stateset!("q0", "q1"), alphabet.clone(), q0.clone(), stateset!(),
+                       delta!(("q0", 'a', "q1")));
+        assert_eq!(trim(&m), M::new(stateset!("q0"), alphabet, q0, stateset!(), delta!()));
+    }
+
+    #[test]
+    fn complete_test() {
+        use super::complete;
+        use automata::M;
+
+        let m = M::new(stateset!("q0", "q1"),
+                       alphabet!('a'),
+                       "q0".to_string(),
+                       stateset!("q1"),
+                       delta!(("q0", 'a', "q1")));
+        let m_expected = M::new(stateset!("q0", "q1", "trap_state"),
+                                alphabet!('a', 'b'),
+                                "q0".to_string(),
+                                stateset!("q1"),
+                                delta!(("q0", 'a', "q1"),
+                                       ("q0", 'b', "trap_state"),
+                                       ("q1", 'a', "trap_state"),
+                                       ("q1", 'b', "trap_state"),
+                                       ("trap_state", 'a', "trap_state"),
+                                       ("trap_state", 'b', "trap_state")));
+
+        let completed = complete(&m, &alphabet!('b'));
+        assert_eq!(completed, m_expected);
+        assert_eq!(complete(&completed, &alphabet!('a')), completed);
+
+        // λ transitions and λ itself are kept
+        let m = M::new(stateset!("q0", "q1"),
+                       alphabet!('a', 'λ'),
+                       "q0".to_string(),
+                       stateset!("q1"),
+                       delta!(("q0", 'λ', "q1"), ("q0", 'a', "q0"), ("q1", 'a', "q1")));
+        assert_eq!(complete(&m, &alphabet!()), m);
+    }
+
+    #[test]
+    fn minify_canonical_test() {
+        use super::{complete, minify, trim};
+        use automata::{M, to_delta};
+
+        // a+ with a missing transition from q0 and an explicit sink
+        // from q1
+        let partial = M::new(stateset!("q0", "q1"),
+                             alphabet!('a', 'b'),
+                             "q0".to_string(),
+                             stateset!("q1"),
+                             delta!(("q0", 'a', "q1"), ("q1", 'a', "q1")));
+        let mixed = M::new(stateset!("q0", "q1", "d"),
+                           alphabet!('a', 'b'),
+                           "q0".to_string(),
+                           stateset!("q1"),
+                           delta!(("q0", 'a', "q1"),
+                                  ("q1", 'a', "q1"),
+                                  ("q1", 'b', "d"),
+                                  ("d", 'a', "d"),
+                                  ("d", 'b', "d")));
+
+        // The same up to the names of the states
+        let same = |a: M, b: M| {
+            assert_eq!(a.k.len(), b.k.len());
+            assert_eq!(a.f.len(), b.f.len());
+            assert_eq!(to_delta(&a).len(), to_delta(&b).len());
+            assert_eq!(a.enumerate(4), b.enumerate(4));
+            a.k.len()
+        };
+
+        assert_eq!(same(minify(&trim(&mixed)), minify(&trim(&partial))), 2);
+
+        let alphabet = alphabet!('a', 'b');
+        let completed = |m: &M| minify(&complete(m, &alphabet));
+        assert_eq!(same(completed(&mixed), completed(&partial)), 3);
+    }
+
     #[test]
     fn pretify_automata_test() {
         use super::pretify_automata;
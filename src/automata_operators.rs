@@ -1,4 +1,5 @@
-use automata::{M, State, StateSet, TRAP_STATE};
+use automata::{M, State, StateSet, Delta, TRAP_STATE};
+use symbol::Symbol;
 use std::collections::BTreeSet;
 
 
@@ -8,7 +9,7 @@ pub fn stateset_name(states: &StateSet) -> String {
 }
 
 
-pub fn lambda_closure(q: &StateSet, m: &M) -> StateSet {
+pub fn lambda_closure<S: Symbol>(q: &StateSet, m: &M<S>) -> StateSet {
     let mut closure: StateSet = q.clone();
     let mut marked: StateSet = BTreeSet::new();
 
@@ -20,7 +21,7 @@ pub fn lambda_closure(q: &StateSet, m: &M) -> StateSet {
             }
             marked.insert(t.clone());
 
-            let next_states = m.get_next_states(t, &'λ');
+            let next_states = m.get_next_states(t, &S::lambda());
             for ns in next_states.iter() {
                 closure.insert(ns.clone());
             }
@@ -33,7 +34,7 @@ pub fn lambda_closure(q: &StateSet, m: &M) -> StateSet {
 }
 
 
-pub fn mover(q: &StateSet, a: char, m: &M) -> StateSet {
+pub fn mover<S: Symbol>(q: &StateSet, a: S, m: &M<S>) -> StateSet {
     let mut x = BTreeSet::new();
     for t in q.iter() {
 
@@ -49,7 +50,7 @@ pub fn mover(q: &StateSet, a: char, m: &M) -> StateSet {
 
 
 
-pub fn afndl_to_afd(m: &M) -> M {
+pub fn afndl_to_afd<S: Symbol>(m: &M<S>) -> M<S> {
     let q0: StateSet = lambda_closure(&stateset!(m.q0), &m);
     let q0_str = stateset_name(&q0);
 
@@ -57,7 +58,7 @@ pub fn afndl_to_afd(m: &M) -> M {
     k.insert(q0);
 
     let mut f = stateset!();
-    let mut delta = delta!();
+    let mut delta: Delta<S> = BTreeSet::new();
     let mut marked = BTreeSet::new();
 
     while k != marked {
@@ -66,14 +67,14 @@ pub fn afndl_to_afd(m: &M) -> M {
             println!("t = {:?}", t);
             marked.insert(t.clone());
             for a in m.alphabet.iter() {
-                let u = mover(&t, *a, &m);
+                let u = mover(t, a.clone(), m);
                 if u.is_empty() {
                     continue;
                 }
 
 
                 k.insert(u.clone());
-                delta.insert((stateset_name(&t), *a, stateset_name(&u)));
+                delta.insert((stateset_name(t), a.clone(), stateset_name(&u)));
             }
         }
     }
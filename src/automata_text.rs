@@ -0,0 +1,469 @@
+// Plain text definition of automatas
+//
+//     # comments run until the end of the line
+//     start q0
+//     final q1 q2
+//     states q3              # states not used anywhere else
+//     alphabet c             # symbols not used by any transition
+//     q0 -a-> q1; q1 -λ-> q2
+//
+// Statements end with a new line or a ';'. State names are made of letters,
+// digits and '_' or they are double quoted ("q1-q2"), a transition reads
+// exactly one char between '-' and '->'.
+
+use std::fmt;
+use std::result;
+
+use automata::{M, State, StateSet, Alphabet, Delta, to_delta};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+pub type ParseResult<T> = result::Result<T, ParseError>;
+
+
+static KEYWORDS: [&str; 4] = ["start", "final", "states", "alphabet"];
+
+struct TextParser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    col: usize,
+}
+
+impl TextParser {
+    fn new(src: &str) -> TextParser {
+        TextParser {
+            chars: src.chars().collect(),
+            index: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).cloned()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.index += 1;
+            if c == '\n' {
+                self.line += 1;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
+        }
+
+        c
+    }
+
+    fn error<T, S: Into<String>>(&self, msg: S) -> ParseResult<T> {
+        Err(ParseError {
+                line: self.line,
+                col: self.col,
+                msg: msg.into(),
+            })
+    }
+
+    // Skips blanks and comments in the current line
+    fn skip_blank(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some() && self.peek() != Some('\n') {
+                    self.bump();
+                }
+            } else if c != '\n' && c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Skips blanks, comments and statement separators
+    fn skip_separators(&mut self) {
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some('\n') | Some(';') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn at_statement_end(&self) -> bool {
+        matches!(self.peek(), None | Some('\n') | Some(';'))
+    }
+
+    fn end_statement(&mut self) -> ParseResult<()> {
+        self.skip_blank();
+        if !self.at_statement_end() {
+            return self.error(format!("expected the end of the statement but found {:?}",
+                                      self.peek().unwrap()));
+        }
+
+        Ok(())
+    }
+
+    fn bare_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if !is_bare_char(c) {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+
+        word
+    }
+
+    fn quoted(&mut self, quote: char) -> ParseResult<String> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated quoted string"),
+                Some('\\') => {
+                    match self.bump() {
+                        Some(c) if c == quote || c == '\\' => s.push(c),
+                        _ => return self.error("invalid escape sequence"),
+                    }
+                }
+                Some(c) if c == quote => return Ok(s),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn state(&mut self) -> ParseResult<State> {
+        self.skip_blank();
+        match self.peek() {
+            Some('"') => self.quoted('"'),
+            Some(c) if is_bare_char(c) => {
+                let word = self.bare_word();
+                if KEYWORDS.contains(&word.as_str()) {
+                    return self.error(format!("{:?} is a keyword, quote it to use it as a state",
+                                              word));
+                }
+                Ok(word)
+            }
+            Some(c) => self.error(format!("expected a state name but found {:?}", c)),
+            None => self.error("expected a state name but found the end of the input"),
+        }
+    }
+
+    fn states(&mut self) -> ParseResult<Vec<State>> {
+        let mut states = vec![];
+        loop {
+            self.skip_blank();
+            if self.at_statement_end() {
+                return Ok(states);
+            }
+            states.push(self.state()?);
+        }
+    }
+
+    fn symbols(&mut self) -> ParseResult<Vec<char>> {
+        let mut symbols = vec![];
+        loop {
+            self.skip_blank();
+            if self.at_statement_end() {
+                return Ok(symbols);
+            }
+
+            let c = if self.peek() == Some('\'') {
+                let s = self.quoted('\'')?;
+                let chars: Vec<char> = s.chars().collect();
+                if chars.len() != 1 {
+                    return self.error(format!("symbol {:?} must be a single char", s));
+                }
+                chars[0]
+            } else {
+                let c = self.bump().unwrap();
+                if let Some(next) = self.peek() {
+                    if !next.is_whitespace() && next != ';' && next != '#' {
+                        return self.error("symbols must be a single char, separate them with \
+                                           spaces");
+                    }
+                }
+                c
+            };
+            symbols.push(c);
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> ParseResult<()> {
+        for e in expected.chars() {
+            match self.peek() {
+                Some(c) if c == e => {
+                    self.bump();
+                }
+                Some(c) => return self.error(format!("expected {:?} but found {:?}", expected, c)),
+                None => {
+                    return self.error(format!("expected {:?} but found the end of the input",
+                                              expected))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn transition(&mut self, s: State) -> ParseResult<(State, char, State)> {
+        self.skip_blank();
+        self.expect("-")?;
+        let c = match self.bump() {
+            Some('\n') | None => return self.error("expected the symbol of the transition"),
+            Some(c) => c,
+        };
+        self.expect("->")?;
+        let ns = self.state()?;
+
+        Ok((s, c, ns))
+    }
+}
+
+fn is_bare_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+
+/// Parses the text definition of an automata,
+/// K is made of every state mentioned in the text.
+pub fn parse_automata(src: &str) -> ParseResult<M> {
+    let mut p = TextParser::new(src);
+
+    let mut q0: Option<State> = None;
+    let mut k: StateSet = stateset!();
+    let mut f: StateSet = stateset!();
+    let mut alphabet: Alphabet = alphabet!();
+    let mut delta: Delta = delta!();
+
+    loop {
+        p.skip_separators();
+        if p.peek().is_none() {
+            break;
+        }
+
+        let (line, col) = (p.line, p.col);
+        let word = if p.peek().is_some_and(is_bare_char) {
+            p.bare_word()
+        } else {
+            String::new()
+        };
+
+        match word.as_str() {
+            "start" => {
+                let state = p.state()?;
+                if let Some(ref q0) = q0 {
+                    return Err(ParseError {
+                                   line,
+                                   col,
+                                   msg: format!("start is already defined as {:?}", q0),
+                               });
+                }
+                k.insert(state.clone());
+                q0 = Some(state);
+            }
+            "final" => {
+                for state in p.states()? {
+                    k.insert(state.clone());
+                    f.insert(state);
+                }
+            }
+            "states" => {
+                for state in p.states()? {
+                    k.insert(state);
+                }
+            }
+            "alphabet" => {
+                for c in p.symbols()? {
+                    if c == 'λ' {
+                        return p.error("λ can not be part of the alphabet");
+                    }
+                    alphabet.insert(c);
+                }
+            }
+            _ => {
+                let s = if word.is_empty() { p.state()? } else { word };
+                let (s, c, ns) = p.transition(s)?;
+                k.insert(s.clone());
+                k.insert(ns.clone());
+                if c != 'λ' {
+                    alphabet.insert(c);
+                }
+                delta.insert((s, c, ns));
+            }
+        }
+
+        p.end_statement()?;
+    }
+
+    let q0 = match q0 {
+        Some(q0) => q0,
+        None => return p.error("missing start statement"),
+    };
+
+    Ok(M::new(k, alphabet, q0, f, delta))
+}
+
+fn state_to_text(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(is_bare_char) && !KEYWORDS.contains(&s) {
+        return s.to_string();
+    }
+
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn symbol_to_text(c: char) -> String {
+    match c {
+        '\'' => "'\\''".to_string(),
+        '\\' => "'\\\\'".to_string(),
+        ';' | '#' => format!("'{}'", c),
+        _ if c.is_whitespace() => format!("'{}'", c),
+        _ => c.to_string(),
+    }
+}
+
+/// Text definition of m, parse_automata(&automata_to_text(&m)) == m.
+pub fn automata_to_text(m: &M) -> String {
+    let delta = to_delta(m);
+
+    let mut text = String::new();
+    text.push_str(&format!("start {}\n", state_to_text(&m.q0)));
+
+    if !m.f.is_empty() {
+        let f: Vec<String> = m.f.iter().map(|s| state_to_text(s)).collect();
+        text.push_str(&format!("final {}\n", f.join(" ")));
+    }
+
+    let mut used_states: StateSet = m.f.clone();
+    used_states.insert(m.q0.clone());
+    let mut used_symbols: Alphabet = alphabet!();
+    for &(ref s, c, ref ns) in &delta {
+        used_states.insert(s.clone());
+        used_states.insert(ns.clone());
+        used_symbols.insert(c);
+    }
+
+    let unused_states: Vec<String> =
+        m.k.difference(&used_states).map(|s| state_to_text(s)).collect();
+    if !unused_states.is_empty() {
+        text.push_str(&format!("states {}\n", unused_states.join(" ")));
+    }
+
+    let unused_symbols: Vec<String> =
+        m.alphabet.difference(&used_symbols).map(|&c| symbol_to_text(c)).collect();
+    if !unused_symbols.is_empty() {
+        text.push_str(&format!("alphabet {}\n", unused_symbols.join(" ")));
+    }
+
+    for (s, c, ns) in delta {
+        text.push_str(&format!("{} -{}-> {}\n", state_to_text(&s), c, state_to_text(&ns)));
+    }
+
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_automata_test() {
+        let m = parse_automata("start q0; final q1; q0 -a-> q1; q1 -λ-> q2").unwrap();
+
+        let m_expected = M::new(stateset!("q0", "q1", "q2"),
+                                alphabet!('a'),
+                                "q0".to_string(),
+                                stateset!("q1"),
+                                delta!(("q0", 'a', "q1"), ("q1", 'λ', "q2")));
+
+        assert_eq!(m, m_expected);
+    }
+
+    #[test]
+    fn parse_automata_multiline_test() {
+        let src = "# a comment\n\
+                   start \"q1-q2\"   # the start\n\
+                   final q3 q4\n\
+                   states q5\n\
+                   alphabet b ';'\n\
+                   \n\
+                   \"q1-q2\" ---> q3\n\
+                   q3 -;-> q4; q4 ->-> q3\n";
+
+        let m = parse_automata(src).unwrap();
+
+        let m_expected = M::new(stateset!("q1-q2", "q3", "q4", "q5"),
+                                alphabet!('-', ';', '>', 'b'),
+                                "q1-q2".to_string(),
+                                stateset!("q3", "q4"),
+                                delta!(("q1-q2", '-', "q3"), ("q3", ';', "q4"), ("q4", '>', "q3")));
+
+        assert_eq!(m, m_expected);
+    }
+
+    #[test]
+    fn parse_automata_errors_test() {
+        let cases = vec![("final q1", (1, 9), "missing start statement"),
+                         ("start q0\nstart q1", (2, 1), "start is already defined as \"q0\""),
+                         ("start q0\nq0 -a- q1", (2, 7), "expected \"->\" but found ' '"),
+                         ("start q0\nq0 a q1", (2, 4), "expected \"-\" but found 'a'"),
+                         ("start q0 q1", (1, 10), "expected the end of the statement but found 'q'"),
+                         ("start \"q0", (1, 10), "unterminated quoted string"),
+                         ("start final", (1, 12), "\"final\" is a keyword, quote it to use it as a state"),
+                         ("start q0\nalphabet ab", (2, 11),
+                          "symbols must be a single char, separate them with spaces")];
+
+        for (src, (line, col), msg) in cases {
+            let err = parse_automata(src).unwrap_err();
+            assert_eq!(err,
+                       ParseError {
+                           line,
+                           col,
+                           msg: msg.to_string(),
+                       },
+                       "In {:?}",
+                       src);
+        }
+    }
+
+    #[test]
+    fn automata_to_text_test() {
+        let m = M::new(stateset!("q0", "q1-q2", "start", "q3"),
+                       alphabet!('a', 'b', ';'),
+                       "q0".to_string(),
+                       stateset!("q1-q2"),
+                       delta!(("q0", 'a', "q1-q2"), ("q1-q2", 'λ', "start")));
+
+        let text = automata_to_text(&m);
+        let expected = "start q0\n\
+                        final \"q1-q2\"\n\
+                        states q3\n\
+                        alphabet ';' b\n\
+                        q0 -a-> \"q1-q2\"\n\
+                        \"q1-q2\" -λ-> \"start\"\n";
+
+        assert_eq!(text, expected);
+        assert_eq!(parse_automata(&text).unwrap(), m);
+    }
+}
@@ -1,46 +1,108 @@
 use std::collections::{BTreeSet, BTreeMap};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 use std::fmt;
 
+use dot;
 
-type Terminal = char;
+
+type Terminal = String;
 type TerminalSet = BTreeSet<Terminal>;
-type NonTerminal = char;
+type NonTerminal = String;
 type NonTerminalSet = BTreeSet<NonTerminal>;
 
-#[derive(Clone, Debug)]
-enum TNT {
+/// Grammar symbol, terminals and nonterminals are named by strings so
+/// symbols such as "id" or "Expr" can be used alongside plain chars.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TNT {
     T(Terminal),
     NT(NonTerminal),
     Lambda,
+    /// End of input marker, it only shows up in FOLLOW sets and parse tables
+    Eof,
+}
+
+/// Terminal fixture for the tests of the grammar modules.
+#[cfg(test)]
+pub(crate) fn t(s: &str) -> TNT {
+    TNT::T(s.to_string())
+}
+
+/// Nonterminal fixture for the tests of the grammar modules.
+#[cfg(test)]
+pub(crate) fn nt(s: &str) -> TNT {
+    TNT::NT(s.to_string())
 }
 
-type Derivation = Vec<TNT>;
-type DerivationVec = Vec<Derivation>;
+pub type Derivation = Vec<TNT>;
+pub type DerivationVec = Vec<Derivation>;
+
+// Symbols of a derivation without Lambda
+pub(crate) fn body(der: &Derivation) -> &[TNT] {
+    if der.as_slice() == [TNT::Lambda] {
+        &der[..0]
+    } else {
+        der
+    }
+}
 
-type Productions<T> = Vec<(NonTerminal, T)>;
-type ProductionsMap = BTreeMap<NonTerminal, DerivationVec>;
+type Productions<T> = Vec<(char, T)>;
+pub type ProductionsMap = BTreeMap<NonTerminal, DerivationVec>;
 
 #[derive(Clone, Debug)]
-struct CFG {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CFGSchema"))]
+pub struct CFG {
+    pub vn: NonTerminalSet,
+    pub vt: TerminalSet,
+    pub p: ProductionsMap,
+    pub s: NonTerminal,
+}
+
+
+/// JSON schema of CFG, validated when deserializing.
+/// Productions map each nonterminal to its derivations, where every
+/// derivation is a list of `{"T": t}`, `{"NT": nt}` or `"Lambda"`.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct CFGSchema {
     vn: NonTerminalSet,
     vt: TerminalSet,
     p: ProductionsMap,
     s: NonTerminal,
 }
 
+#[cfg(feature = "serde")]
+impl TryFrom<CFGSchema> for CFG {
+    type Error = String;
+
+    fn try_from(schema: CFGSchema) -> Result<CFG, String> {
+        CFG::check(&schema.vn, &schema.vt, &schema.p, &schema.s)?;
+
+        Ok(CFG {
+               vn: schema.vn,
+               vt: schema.vt,
+               p: schema.p,
+               s: schema.s,
+           })
+    }
+}
+
 
 impl CFG {
-    pub fn new<T: Into<String> + fmt::Debug>(vn: NonTerminalSet,
-                                             vt: TerminalSet,
+    /// Grammar over single char symbols, every char of a derivation
+    /// string is a symbol and the empty string stands for Lambda.
+    pub fn new<T: Into<String> + fmt::Debug>(vn: BTreeSet<char>,
+                                             vt: BTreeSet<char>,
                                              p: Productions<T>,
-                                             s: NonTerminal)
+                                             s: char)
                                              -> CFG {
-        let mut p_map: ProductionsMap = BTreeMap::new();
-
         if !vn.is_disjoint(&vt) {
             panic!("VN and VT must be disjoint.\nVN: {:?} \nVT: {:?}", vn, vt);
         }
 
+        let mut productions = vec![];
         for (nt, der_str) in p {
             if !vn.contains(&nt) {
                 panic!("NonTerminal in production rule does not belong to VN {:?} -> {:?} \n {:?}",
@@ -49,24 +111,22 @@ impl CFG {
                        vn);
             }
 
-            let dervec = p_map.entry(nt).or_insert(vec![]);
-
             let mut der: Derivation = vec![];
 
             let der_string = der_str.into();
 
-            if der_string.len() == 0 {
+            if der_string.is_empty() {
                 der.push(TNT::Lambda);
             }
 
             for c in der_string.chars() {
                 if vn.contains(&c) {
-                    der.push(TNT::NT(c.clone()));
+                    der.push(TNT::NT(c.to_string()));
                     continue;
                 }
 
                 if vt.contains(&c) {
-                    der.push(TNT::T(c.clone()));
+                    der.push(TNT::T(c.to_string()));
                     continue;
                 }
 
@@ -76,21 +136,111 @@ impl CFG {
                        c);
             }
 
-            dervec.push(der);
+            productions.push((nt.to_string(), der));
         }
 
-        CFG {
-            vn: vn,
-            vt: vt,
-            p: p_map,
-            s: s,
+        CFG::from_derivations(vn.iter().map(|c| c.to_string()).collect(),
+                              vt.iter().map(|c| c.to_string()).collect(),
+                              productions,
+                              s.to_string())
+    }
+
+    /// Grammar over symbols of any length, derivations are given
+    /// already split into symbols. Panics if the grammar is not valid.
+    pub fn from_derivations(vn: NonTerminalSet,
+                            vt: TerminalSet,
+                            p: Vec<(NonTerminal, Derivation)>,
+                            s: NonTerminal)
+                            -> CFG {
+        match CFG::try_from_derivations(vn, vt, p, s) {
+            Ok(g) => g,
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    /// Like `from_derivations` but returns the `CFG::check` error
+    /// instead of panicking.
+    pub fn try_from_derivations(vn: NonTerminalSet,
+                                vt: TerminalSet,
+                                p: Vec<(NonTerminal, Derivation)>,
+                                s: NonTerminal)
+                                -> Result<CFG, String> {
+        let mut p_map: ProductionsMap = BTreeMap::new();
+        for (nt, der) in p {
+            p_map.entry(nt).or_default().push(der);
+        }
+
+        CFG::check(&vn, &vt, &p_map, &s)?;
+
+        Ok(CFG {
+               vn,
+               vt,
+               p: p_map,
+               s,
+           })
+    }
+
+    /// Validates the components of a grammar, VN and VT must be
+    /// disjoint, S must be a nonterminal and every production must
+    /// only use symbols of VN and VT, Lambda being allowed only
+    /// as a whole derivation.
+    pub fn check(vn: &NonTerminalSet,
+                 vt: &TerminalSet,
+                 p: &ProductionsMap,
+                 s: &NonTerminal)
+                 -> Result<(), String> {
+        if !vn.is_disjoint(vt) {
+            return Err(format!("VN and VT must be disjoint.\nVN: {:?} \nVT: {:?}", vn, vt));
+        }
+
+        if !vn.contains(s) {
+            return Err(format!("S {:?} does not belong to VN {:?}", s, vn));
         }
+
+        for (nt, dervec) in p {
+            if !vn.contains(nt) {
+                return Err(format!("NonTerminal in production rule does not belong to VN {:?}",
+                                   nt));
+            }
+
+            for der in dervec {
+                for e in der {
+                    let valid = match *e {
+                        TNT::T(ref t) => vt.contains(t),
+                        TNT::NT(ref nt) => vn.contains(nt),
+                        TNT::Lambda => der.len() == 1,
+                        TNT::Eof => false,
+                    };
+
+                    if !valid {
+                        return Err(format!("Symbol in derivation {:?} -> {:?} does not belong \
+                                            to VN or VT",
+                                           nt,
+                                           derivation_to_text(der)));
+                    }
+                }
+            }
+        }
+
+        if vn.contains(&String::new()) || vt.contains(&String::new()) {
+            return Err("Symbols can not be empty".to_string());
+        }
+
+        Ok(())
     }
 
-    //pub fn get_nt_derivations(&self, nt: &NonTerminal) -> DerivationVec {
-    ////TODO: optimize the case where the nt is not found
-    //self.p.get(nt).unwrap().clone()
-    //}
+    /// Productions numbered in the order of `p`, the index of a
+    /// production in this list identifies it in FIRST+ sets and parse tables.
+    pub fn productions(&self) -> Vec<(NonTerminal, Derivation)> {
+        let mut productions = vec![];
+        for (nt, dervec) in &self.p {
+            for der in dervec {
+                productions.push((nt.clone(), der.clone()));
+            }
+        }
+
+        productions
+    }
 }
 
 
@@ -105,8 +255,7 @@ impl fmt::Display for CFG {
         write!(f, "Productions: \n").unwrap();
         for (nt, dervec) in &self.p {
             for der in dervec {
-                let der_string = derivation_to_string(der);
-                write!(f, "{:?} -> {:?} \n", nt, der_string).unwrap();
+                write!(f, "{} -> {} \n", nt, derivation_to_text(der)).unwrap();
             }
         }
 
@@ -115,10 +264,10 @@ impl fmt::Display for CFG {
 }
 
 
-#[derive(Debug, Clone)]
-struct TNode {
-    val: TNT,
-    children: Vec<TNode>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TNode {
+    pub val: TNT,
+    pub children: Vec<TNode>,
 }
 
 impl TNode {
@@ -128,57 +277,81 @@ impl TNode {
             children: children,
         }
     }
-}
 
+    /// Terminals of the leaves from left to right, the sentence
+    /// the tree derives.
+    pub fn leaves(&self) -> Vec<String> {
+        let mut leaves = vec![];
+        let mut pending = vec![self];
+        while let Some(node) = pending.pop() {
+            if let TNT::T(ref t) = node.val {
+                leaves.push(t.clone());
+            }
+            pending.extend(node.children.iter().rev());
+        }
 
-impl fmt::Display for TNode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let width = 90;
-        let cwidth = width / self.children.len();
-        write!(f, "\n").unwrap();
-        write!(f, "{:^width$}", self.val.to_string(), width = width).unwrap();
-        write!(f, "\n").unwrap();
+        leaves
+    }
+
+    /// Graphviz DOT representation of the derivation tree.
+    /// Terminals and Lambda are drawn as grey boxes and nonterminals as ellipses.
+    pub fn to_dot(&self) -> String {
+        let mut body = String::new();
+        let mut next_id = 0;
+        self.dot_walk(&mut next_id, &mut body);
+
+        dot::digraph("derivation", &body)
+    }
 
-        write!(f, "\n").unwrap();
-        write!(f, "\n").unwrap();
+    fn dot_walk(&self, next_id: &mut usize, body: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
 
+        match self.val {
+            TNT::NT(ref nt) => body.push_str(&dot::non_terminal_node(id, nt)),
+            TNT::T(ref t) => body.push_str(&dot::terminal_node(id, t)),
+            TNT::Lambda => body.push_str(&dot::terminal_node(id, "λ")),
+            TNT::Eof => body.push_str(&dot::terminal_node(id, "eof")),
+        }
 
         for c in &self.children {
-            write!(f, "{:^width$}", c.val.to_string(), width = cwidth).unwrap();
+            let child_id = c.dot_walk(next_id, body);
+            body.push_str(&dot::edge(id, child_id));
         }
 
-        write!(f, "\n")
+        id
     }
-}
-
 
+    // Draws the subtrees one symbol per line below their parent
+    fn fmt_children(&self, f: &mut fmt::Formatter, indent: &str) -> fmt::Result {
+        for (i, c) in self.children.iter().enumerate() {
+            let last = i == self.children.len() - 1;
+            let (branch, nested) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
 
-impl TNT {
-    fn to_char(&self) -> char {
-        match *self {
-            TNT::T(t) => t,
-            TNT::NT(nt) => nt,
-            // TODO: proper lambda symbol
-            TNT::Lambda => '&',
+            writeln!(f, "{}{}{}", indent, branch, derivation_to_text(&vec![c.val.clone()]))?;
+            c.fmt_children(f, &format!("{}{}", indent, nested))?;
         }
+
+        Ok(())
     }
+}
 
-    fn to_string(&self) -> String {
-        match *self {
-            TNT::T(t) => format!("T('{}')", t),
-            TNT::NT(nt) => format!("NT('{}')", nt),
-            TNT::Lambda => "Lambda".to_string(),
-        }
+
+impl fmt::Display for TNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", derivation_to_text(&vec![self.val.clone()]))?;
+        self.fmt_children(f, "")
     }
 }
 
 
-fn derivation_to_string(der: &Derivation) -> String {
+/// Concatenation of the symbols of a derivation, Lambda is the empty string.
+pub fn derivation_to_string(der: &Derivation) -> String {
     let mut der_string = String::new();
     for e in der {
         match *e {
-            TNT::T(t) => der_string.push(t),
-            TNT::NT(nt) => der_string.push(nt),
+            TNT::T(ref t) => der_string.push_str(t),
+            TNT::NT(ref nt) => der_string.push_str(nt),
             _ => {}
         }
     }
@@ -186,16 +359,42 @@ fn derivation_to_string(der: &Derivation) -> String {
     der_string
 }
 
-//Creates a single level derivation tree for the given Derivation
-fn tree(nt: &NonTerminal, der: &Derivation) -> TNode {
-    let mut children: Vec<TNode> = vec![];
-    for e in der {
-        let node = TNode::new(e.clone(), vec![]);
-        children.push(node);
+/// Symbols of a derivation separated by spaces, Lambda is written as λ.
+pub fn derivation_to_text(der: &Derivation) -> String {
+    der.iter()
+        .map(|e| match *e {
+                 TNT::T(ref t) => t.as_str(),
+                 TNT::NT(ref nt) => nt.as_str(),
+                 TNT::Lambda => "λ",
+                 TNT::Eof => "eof",
+             })
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+
+/// Error shared by the parsers over a stream of terminals, the token at
+/// `position` (Eof when the input ended) is not one of the `expected` terminals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub position: usize,
+    pub found: TNT,
+    pub expected: BTreeSet<TNT>,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected: Vec<String> = self.expected
+            .iter()
+            .map(|e| derivation_to_text(&vec![e.clone()]))
+            .collect();
+
+        write!(f,
+               "token {}: expected {} but found {}",
+               self.position,
+               expected.join(", "),
+               derivation_to_text(&vec![self.found.clone()]))
     }
-    // TODO: check if the nt is effectively a nonterminal or not
-    let root = TNode::new(TNT::NT(nt.clone()), children);
-    root
 }
 
 
@@ -204,23 +403,97 @@ fn tree(nt: &NonTerminal, der: &Derivation) -> TNode {
 mod tests {
     #[test]
     fn cfg_new_test() {
-        use super::{CFG, NonTerminalSet, TerminalSet, NonTerminal, Productions};
+        use super::{CFG, TNT, Productions};
 
-        let vn: NonTerminalSet = charset!('S');
-        let vt: TerminalSet = charset!('a', '(', ')');
-        let s: NonTerminal = 'S';
-        let p: Productions<&'static str> = vec![('S', "(S)"), ('S', "a")];
+        let vn = charset!('S');
+        let vt = charset!('a', '(', ')');
+        let s = 'S';
+        let p: Productions<&'static str> = vec![('S', "(S)"), ('S', "a"), ('S', "")];
 
         let g = CFG::new(vn, vt, p, s);
         println!("Resulted grammar {}", g);
+
+        assert_eq!(g.s, "S");
+        assert_eq!(g.vt, stateset!("a", "(", ")"));
+        assert_eq!(g.p["S"],
+                   vec![vec![TNT::T("(".to_string()), TNT::NT("S".to_string()), TNT::T(")".to_string())],
+                        vec![TNT::T("a".to_string())],
+                        vec![TNT::Lambda]]);
     }
 
     #[test]
-    fn tree_test() {
-        use super::{TNT, tree};
+    fn from_derivations_test() {
+        use super::{CFG, TNT, derivation_to_text};
+
+        let expr = || TNT::NT("Expr".to_string());
+        let id = || TNT::T("id".to_string());
+        let plus = || TNT::T("+".to_string());
+
+        let g = CFG::from_derivations(stateset!("Expr"),
+                                      stateset!("id", "+"),
+                                      vec![("Expr".to_string(), vec![expr(), plus(), id()]),
+                                           ("Expr".to_string(), vec![id()])],
+                                      "Expr".to_string());
+
+        assert_eq!(g.p["Expr"].len(), 2);
+        assert_eq!(derivation_to_text(&g.p["Expr"][0]), "Expr + id");
+        assert!(g.to_string().contains("Expr -> Expr + id"));
+    }
+
+    #[test]
+    fn check_test() {
+        use super::{CFG, TNT};
+        use std::collections::BTreeMap;
+
+        let mut p = BTreeMap::new();
+        p.insert("S".to_string(), vec![vec![TNT::T("id".to_string()), TNT::Lambda]]);
+        assert!(CFG::check(&stateset!("S"), &stateset!("id"), &p, &"S".to_string()).is_err());
+
+        p.insert("S".to_string(), vec![vec![TNT::T("num".to_string())]]);
+        assert!(CFG::check(&stateset!("S"), &stateset!("id"), &p, &"S".to_string()).is_err());
+
+        p.insert("S".to_string(), vec![vec![TNT::T("id".to_string())]]);
+        assert!(CFG::check(&stateset!("S"), &stateset!("id"), &p, &"S".to_string()).is_ok());
+        assert!(CFG::check(&stateset!("S"), &stateset!("id"), &p, &"A".to_string()).is_err());
+        assert!(CFG::check(&stateset!("S", "id"), &stateset!("id"), &p, &"S".to_string())
+                    .is_err());
+    }
+
+    #[test]
+    fn tnode_display_test() {
+        use super::{TNT, TNode};
+
+        let leaf = TNode::new(TNT::T("a".to_string()), vec![]);
+        assert_eq!(leaf.to_string(), "a\n");
+
+        let t = TNode::new(TNT::NT("S".to_string()),
+                           vec![TNode::new(TNT::T("(".to_string()), vec![]),
+                                TNode::new(TNT::NT("S".to_string()),
+                                           vec![TNode::new(TNT::Lambda, vec![])]),
+                                TNode::new(TNT::T(")".to_string()), vec![]),
+                                TNode::new(TNT::NT("S".to_string()),
+                                           vec![TNode::new(TNT::T("a".to_string()), vec![])])]);
+
+        assert_eq!(t.to_string(),
+                   "S\n├── (\n├── S\n│   └── λ\n├── )\n└── S\n    └── a\n");
+        assert_eq!(t.leaves(), vec!["(", ")", "a"]);
+    }
+
+    #[test]
+    fn to_dot_test() {
+        use super::{TNT, TNode};
+
+        let t = TNode::new(TNT::NT("S".to_string()),
+                           vec![TNode::new(TNT::T("a".to_string()), vec![]),
+                                TNode::new(TNT::NT("A".to_string()),
+                                           vec![TNode::new(TNT::Lambda, vec![])])]);
+
+        let expected = "digraph derivation {\n    ordering=out;\n    n0 [label=\"S\", \
+                        shape=ellipse];\n    n1 [label=\"a\", shape=box, style=filled, \
+                        fillcolor=lightgrey];\n    n0 -> n1;\n    n2 [label=\"A\", \
+                        shape=ellipse];\n    n3 [label=\"λ\", shape=box, style=filled, \
+                        fillcolor=lightgrey];\n    n2 -> n3;\n    n0 -> n2;\n}\n";
 
-        let der = vec![TNT::T('('), TNT::NT('S'), TNT::T(')')];
-        let t = tree(&'S', &der);
-        println!("Resulted tree {}", t);
+        assert_eq!(t.to_dot(), expected);
     }
 }
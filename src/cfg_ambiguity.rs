@@ -0,0 +1,244 @@
+// Bounded ambiguity check.
+//
+// Leftmost derivations are explored breadth first from S. Two different
+// derivations that reach the same sentential form can both be completed
+// the same way into a sentence, which then has two leftmost derivations
+// and so two derivation trees. Forms that could only derive sentences
+// longer than the bound are pruned, so the search is exhaustive up to it.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use cfg::{CFG, TNT, TNode, Derivation, body};
+use cfg_derivation::DerivationOrder;
+
+
+/// A sentence with two different leftmost derivations, given as
+/// indexes of `CFG::productions`, and their derivation trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    pub sentence: Vec<String>,
+    pub derivations: [Vec<usize>; 2],
+    pub trees: [TNode; 2],
+}
+
+impl fmt::Display for Ambiguity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sentence = if self.sentence.is_empty() {
+            "λ".to_string()
+        } else {
+            self.sentence.join(" ")
+        };
+        let derivations: Vec<String> = self.derivations
+            .iter()
+            .map(|d| d.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(" "))
+            .collect();
+
+        write!(f,
+               "{} has two leftmost derivations: {} and {}",
+               sentence,
+               derivations[0],
+               derivations[1])
+    }
+}
+
+impl CFG {
+    /// Looks for a sentence of at most `max_len` terminals with two
+    /// leftmost derivations. Sentential forms are also limited to
+    /// `max_len` plus the number of nonterminals, as nullable
+    /// nonterminals could make them grow without bound.
+    pub fn find_ambiguity(&self, max_len: usize) -> Option<Ambiguity> {
+        let productions = self.productions();
+        let (shortest, completion) = shortest_sentences(&productions);
+        if !shortest.contains_key(&self.s) {
+            return None;
+        }
+        let max_symbols = max_len + self.vn.len();
+
+        let start = vec![TNT::NT(self.s.clone())];
+        let mut visited: BTreeMap<Vec<TNT>, Vec<usize>> = BTreeMap::new();
+        visited.insert(start.clone(), vec![]);
+        let mut pending = VecDeque::new();
+        pending.push_back((start, vec![]));
+
+        while let Some((form, derivation)) = pending.pop_front() {
+            let leftmost = match form.iter().position(|e| matches!(*e, TNT::NT(_))) {
+                Some(leftmost) => leftmost,
+                None => continue,
+            };
+
+            for (index, (nt, der)) in productions.iter().enumerate() {
+                if form[leftmost] != TNT::NT(nt.clone()) {
+                    continue;
+                }
+
+                let mut next = form[..leftmost].to_vec();
+                next.extend(body(der).iter().cloned());
+                next.extend(form[leftmost + 1..].iter().cloned());
+
+                let length = next.iter()
+                    .map(|e| match *e {
+                             TNT::NT(ref nt) => shortest.get(nt).cloned(),
+                             _ => Some(1),
+                         })
+                    .sum::<Option<usize>>();
+                match length {
+                    Some(length) if length <= max_len && next.len() <= max_symbols => {}
+                    _ => continue,
+                }
+
+                let mut next_derivation = derivation.clone();
+                next_derivation.push(index);
+
+                if let Some(other) = visited.get(&next) {
+                    let rest = complete(&productions, &completion, &next);
+                    let derivations = [[other.clone(), rest.clone()].concat(),
+                                       [next_derivation, rest].concat()];
+                    let tree = |steps: &[usize]| {
+                        self.derivation_tree(DerivationOrder::Leftmost, steps).unwrap()
+                    };
+                    let trees = [tree(&derivations[0]), tree(&derivations[1])];
+
+                    let sentence = trees[0].leaves();
+
+                    return Some(Ambiguity {
+                                    sentence,
+                                    derivations,
+                                    trees,
+                                });
+                }
+
+                visited.insert(next.clone(), next_derivation.clone());
+                pending.push_back((next, next_derivation));
+            }
+        }
+
+        None
+    }
+}
+
+
+// Length of the shortest sentence of every generating nonterminal and the
+// production that starts it. Nonterminals are settled in order of length
+// like in Knuth's generalization of Dijkstra's algorithm, so following the
+// chosen productions always ends.
+fn shortest_sentences(productions: &[(String, Derivation)])
+                      -> (BTreeMap<String, usize>, BTreeMap<String, usize>) {
+    let mut shortest: BTreeMap<String, usize> = BTreeMap::new();
+    let mut completion = BTreeMap::new();
+
+    loop {
+        let mut best: Option<(usize, &String, usize)> = None;
+        for (index, (nt, der)) in productions.iter().enumerate() {
+            if shortest.contains_key(nt) {
+                continue;
+            }
+
+            let length = body(der)
+                .iter()
+                .map(|e| match *e {
+                         TNT::NT(ref nt) => shortest.get(nt).cloned(),
+                         _ => Some(1),
+                     })
+                .sum::<Option<usize>>();
+            match (length, best) {
+                (Some(length), Some((best_length, _, _))) if length < best_length => {
+                    best = Some((length, nt, index))
+                }
+                (Some(length), None) => best = Some((length, nt, index)),
+                _ => {}
+            }
+        }
+
+        match best {
+            Some((length, nt, index)) => {
+                shortest.insert(nt.clone(), length);
+                completion.insert(nt.clone(), index);
+            }
+            None => return (shortest, completion),
+        }
+    }
+}
+
+// Leftmost derivation of the shortest sentence of the form
+fn complete(productions: &[(String, Derivation)],
+            completion: &BTreeMap<String, usize>,
+            form: &[TNT])
+            -> Vec<usize> {
+    let mut derivation = vec![];
+    let mut pending: Vec<TNT> = form.iter().rev().cloned().collect();
+
+    while let Some(e) = pending.pop() {
+        if let TNT::NT(nt) = e {
+            let index = completion[&nt];
+            derivation.push(index);
+            pending.extend(body(&productions[index].1).iter().rev().cloned());
+        }
+    }
+
+    derivation
+}
+
+
+#[cfg(test)]
+mod tests {
+    use cfg_text::parse_grammar;
+
+    #[test]
+    fn find_ambiguity_test() {
+        let g = parse_grammar("E -> E \"+\" E | \"a\"").unwrap();
+        let ambiguity = g.find_ambiguity(5).unwrap();
+
+        assert_eq!(ambiguity.sentence, vec!["a", "+", "a", "+", "a"]);
+        assert!(ambiguity.derivations[0] != ambiguity.derivations[1]);
+        assert!(ambiguity.trees[0] != ambiguity.trees[1]);
+        assert_eq!(ambiguity.to_string(),
+                   "a + a + a has two leftmost derivations: 0 0 1 1 1 and 0 1 0 1 1");
+
+        // Every tree yields the sentence
+        for tree in &ambiguity.trees {
+            assert_eq!(tree.leaves(), ambiguity.sentence);
+        }
+
+        assert_eq!(g.find_ambiguity(3), None);
+    }
+
+    #[test]
+    fn dangling_else_test() {
+        let g = parse_grammar("S -> \"if\" \"c\" \"then\" S | \"if\" \"c\" \"then\" S \"else\" S | \
+                               \"s\"")
+            .unwrap();
+        assert_eq!(g.find_ambiguity(8), None);
+        let ambiguity = g.find_ambiguity(9).unwrap();
+
+        assert_eq!(ambiguity.sentence.join(" "), "if c then if c then s else s");
+        let attached: Vec<usize> = ambiguity.trees
+            .iter()
+            .map(|t| t.children.len())
+            .collect();
+        assert_eq!(attached, vec![4, 6]);
+    }
+
+    #[test]
+    fn unambiguous_test() {
+        let g = parse_grammar("E -> E \"+\" T | T ; T -> T \"*\" F | F ; \
+                               F -> \"(\" E \")\" | \"a\"")
+            .unwrap();
+        assert_eq!(g.find_ambiguity(7), None);
+
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        assert_eq!(g.find_ambiguity(8), None);
+    }
+
+    #[test]
+    fn lambda_and_cycle_ambiguity_test() {
+        let g = parse_grammar("S -> A | B ; A -> Lambda ; B -> Lambda").unwrap();
+        let ambiguity = g.find_ambiguity(0).unwrap();
+        assert!(ambiguity.sentence.is_empty());
+        assert!(ambiguity.to_string().starts_with("λ has two"));
+
+        let g = parse_grammar("S -> A | \"a\" ; A -> S").unwrap();
+        let ambiguity = g.find_ambiguity(1).unwrap();
+        assert_eq!(ambiguity.sentence, vec!["a"]);
+    }
+}
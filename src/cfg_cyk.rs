@@ -0,0 +1,256 @@
+// Cocke–Younger–Kasami membership test. The grammar is converted to CNF
+// and table[i][l - 1] holds the nonterminals that derive the l symbols
+// of the input starting at position i, so the input belongs to the
+// language when S is in table[0][n - 1]. The derivation trees are built
+// over the CNF grammar and the nonterminals it adds are then collapsed,
+// so they only show the nonterminals of the original grammar.
+
+use std::collections::BTreeSet;
+
+use cfg::{CFG, TNT, TNode};
+
+
+pub type CykTable = Vec<Vec<BTreeSet<String>>>;
+
+#[derive(Debug, Clone)]
+pub struct Cyk {
+    /// CNF grammar the table refers to
+    pub grammar: CFG,
+    pub symbols: Vec<String>,
+    pub table: CykTable,
+    pub member: bool,
+    /// Nonterminals and start symbol of the original grammar
+    vn: BTreeSet<String>,
+    s: String,
+}
+
+impl CFG {
+    /// Runs CYK over the chars of the input, see `cyk_symbols`
+    /// for grammars with longer terminals.
+    pub fn cyk(&self, input: &str) -> Cyk {
+        let symbols: Vec<String> = input.chars().map(|c| c.to_string()).collect();
+        self.cyk_symbols(&symbols)
+    }
+
+    pub fn cyk_symbols<T: AsRef<str>>(&self, symbols: &[T]) -> Cyk {
+        let grammar = self.to_cnf();
+        let symbols: Vec<String> = symbols.iter().map(|s| s.as_ref().to_string()).collect();
+        let n = symbols.len();
+
+        let mut table: CykTable = (0..n).map(|i| vec![BTreeSet::new(); n - i]).collect();
+
+        for (i, a) in symbols.iter().enumerate() {
+            for (nt, dervec) in &grammar.p {
+                if dervec.contains(&vec![TNT::T(a.clone())]) {
+                    table[i][0].insert(nt.clone());
+                }
+            }
+        }
+
+        for l in 2..n + 1 {
+            for i in 0..n - l + 1 {
+                for k in 1..l {
+                    for (nt, dervec) in &grammar.p {
+                        let derives = dervec.iter().any(|der| match der.as_slice() {
+                            [TNT::NT(ref b), TNT::NT(ref c)] => {
+                                table[i][k - 1].contains(b) && table[i + k][l - k - 1].contains(c)
+                            }
+                            _ => false,
+                        });
+
+                        if derives {
+                            table[i][l - 1].insert(nt.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        let member = if n == 0 {
+            grammar.p[&grammar.s].contains(&vec![TNT::Lambda])
+        } else {
+            table[0][n - 1].contains(&grammar.s)
+        };
+
+        Cyk {
+            grammar,
+            symbols,
+            table,
+            member,
+            vn: self.vn.clone(),
+            s: self.s.clone(),
+        }
+    }
+}
+
+impl Cyk {
+    /// One derivation tree of the input when it belongs to the language.
+    pub fn tree(&self) -> Option<TNode> {
+        self.trees_limited(1).pop()
+    }
+
+    /// Every derivation tree of the input, more than one means the
+    /// grammar is ambiguous. The trees only have the nonterminals of the
+    /// original grammar, but the unit and λ productions removed by the
+    /// CNF conversion do not show up in them. The count can grow
+    /// exponentially with the length of the input.
+    pub fn trees(&self) -> Vec<TNode> {
+        self.trees_limited(usize::MAX)
+    }
+
+    fn trees_limited(&self, limit: usize) -> Vec<TNode> {
+        if !self.member {
+            return vec![];
+        }
+
+        let s = &self.grammar.s;
+        let cnf_trees = if self.symbols.is_empty() {
+            vec![TNode::new(TNT::NT(s.clone()), vec![TNode::new(TNT::Lambda, vec![])])]
+        } else {
+            self.build(s, 0, self.symbols.len(), limit)
+        };
+
+        // The start symbol may be a new one when S is nullable
+        let mut trees = vec![];
+        for tree in cnf_trees {
+            let children = tree.children.into_iter().flat_map(|c| self.collapse(c)).collect();
+            let tree = TNode::new(TNT::NT(self.s.clone()), children);
+            if !trees.contains(&tree) {
+                trees.push(tree);
+            }
+        }
+
+        trees
+    }
+
+    // Replaces the nodes of nonterminals added by the CNF conversion,
+    // A_1 or T_a, with their children
+    fn collapse(&self, tree: TNode) -> Vec<TNode> {
+        let children = tree.children.into_iter().flat_map(|c| self.collapse(c)).collect();
+        match tree.val {
+            TNT::NT(ref nt) if !self.vn.contains(nt) => children,
+            val => vec![TNode::new(val, children)],
+        }
+    }
+
+    // Trees of nt deriving the l symbols starting at i
+    fn build(&self, nt: &str, i: usize, l: usize, limit: usize) -> Vec<TNode> {
+        let root = TNT::NT(nt.to_string());
+        if l == 1 {
+            let leaf = TNode::new(TNT::T(self.symbols[i].clone()), vec![]);
+            return vec![TNode::new(root, vec![leaf])];
+        }
+
+        let mut trees = vec![];
+        for k in 1..l {
+            for der in &self.grammar.p[nt] {
+                let (b, c) = match der.as_slice() {
+                    [TNT::NT(ref b), TNT::NT(ref c)] => (b, c),
+                    _ => continue,
+                };
+                if !self.table[i][k - 1].contains(b) || !self.table[i + k][l - k - 1].contains(c) {
+                    continue;
+                }
+
+                for left in self.build(b, i, k, limit) {
+                    for right in self.build(c, i + k, l - k, limit - trees.len()) {
+                        trees.push(TNode::new(root.clone(), vec![left.clone(), right]));
+                        if trees.len() == limit {
+                            return trees;
+                        }
+                    }
+                }
+            }
+        }
+
+        trees
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::{t, nt};
+    use cfg_text::parse_grammar;
+
+    #[test]
+    fn cyk_test() {
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+
+        for &(input, expected) in &[("", true),
+                                    ("()", true),
+                                    ("(())()", true),
+                                    ("(()", false),
+                                    (")(", false),
+                                    ("()a", false)] {
+            let cyk = g.cyk(input);
+            assert_eq!(cyk.member, expected, "In {:?}", input);
+
+            let tree = cyk.tree();
+            assert_eq!(tree.is_some(), expected);
+            if let Some(tree) = tree {
+                assert_eq!(tree.leaves().concat(), input);
+            }
+        }
+    }
+
+    #[test]
+    fn cyk_table_test() {
+        // Hopcroft, Motwani, Ullman example 7.34
+        let g = parse_grammar("S -> A B | B C ; A -> B A | \"a\" ; B -> C C | \"b\" ; C -> A B | \"a\"")
+            .unwrap();
+        let cyk = g.cyk("baaba");
+
+        assert!(cyk.member);
+        assert_eq!(cyk.table[0][0], stateset!("B"));
+        assert_eq!(cyk.table[1][0], stateset!("A", "C"));
+        assert_eq!(cyk.table[0][1], stateset!("S", "A"));
+        assert_eq!(cyk.table[1][1], stateset!("B"));
+        assert_eq!(cyk.table[2][1], stateset!("S", "C"));
+        assert_eq!(cyk.table[3][1], stateset!("S", "A"));
+        assert_eq!(cyk.table[0][2], stateset!());
+        assert_eq!(cyk.table[1][2], stateset!("B"));
+        assert_eq!(cyk.table[2][2], stateset!("B"));
+        assert_eq!(cyk.table[0][3], stateset!());
+        assert_eq!(cyk.table[1][3], stateset!("S", "A", "C"));
+        assert_eq!(cyk.table[0][4], stateset!("S", "A", "C"));
+    }
+
+    #[test]
+    fn cyk_ambiguous_trees_test() {
+        let g = parse_grammar("E -> E \"+\" E | \"a\"").unwrap();
+
+        assert_eq!(g.cyk("a+a").trees().len(), 1);
+        // Catalan numbers count the ways to parenthesize the sums
+        assert_eq!(g.cyk("a+a+a").trees().len(), 2);
+        assert_eq!(g.cyk("a+a+a+a").trees().len(), 5);
+        assert_eq!(g.cyk("a+a+").trees().len(), 0);
+
+        let trees = g.cyk("a+a+a").trees();
+        assert!(trees[0] != trees[1]);
+
+        // E_1 and T_+ of the CNF grammar are collapsed
+        let labels: Vec<TNT> = trees[0].children.iter().map(|c| c.val.clone()).collect();
+        assert_eq!(labels, vec![nt("E"), t("+"), nt("E")]);
+        assert_eq!(trees[0].to_string().matches('E').count(), 5);
+    }
+
+    #[test]
+    fn cyk_nullable_start_tree_test() {
+        let g = parse_grammar("S -> \"a\" S \"b\" | Lambda").unwrap();
+        let tree = g.cyk("ab").tree().unwrap();
+
+        // S' and T_a are not in the tree, S -> λ was removed by CNF
+        assert_eq!(tree.to_string(), "S\n├── a\n└── b\n");
+        assert_eq!(g.cyk("").tree().unwrap().to_string(), "S\n└── λ\n");
+    }
+
+    #[test]
+    fn cyk_symbols_test() {
+        let g = parse_grammar("Stmt -> \"if\" \"cond\" \"then\" Stmt | \"skip\"").unwrap();
+
+        assert!(g.cyk_symbols(&["if", "cond", "then", "skip"]).member);
+        assert!(!g.cyk_symbols(&["if", "cond", "skip"]).member);
+    }
+}
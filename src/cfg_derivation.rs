@@ -0,0 +1,250 @@
+// Step by step derivations.
+//
+// A DerivationSequence starts at S and every step rewrites the leftmost or
+// the rightmost nonterminal of the sentential form with a production given
+// by its index in `CFG::productions`. The applied productions are enough
+// to rebuild the derivation tree: a leftmost derivation expands the
+// nonterminals in preorder and a rightmost one in preorder taking the
+// children from right to left.
+
+use std::fmt;
+
+use cfg::{CFG, TNT, TNode, Derivation, body, derivation_to_text};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DerivationOrder {
+    Leftmost,
+    Rightmost,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationError {
+    UnknownProduction(usize),
+    /// The sentential form has no nonterminal left to rewrite
+    Finished,
+    /// The production does not rewrite the nonterminal next in order
+    WrongNonTerminal { production: usize, expected: String },
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DerivationError::UnknownProduction(production) => {
+                write!(f, "there is no production {}", production)
+            }
+            DerivationError::Finished => write!(f, "the sentential form has no nonterminals"),
+            DerivationError::WrongNonTerminal { production, ref expected } => {
+                write!(f, "production {} does not rewrite {}", production, expected)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DerivationSequence {
+    pub productions: Vec<(String, Derivation)>,
+    pub order: DerivationOrder,
+    /// Every sentential form so far, the first one is S
+    pub forms: Vec<Vec<TNT>>,
+    pub steps: Vec<usize>,
+}
+
+impl CFG {
+    pub fn derive(&self, order: DerivationOrder) -> DerivationSequence {
+        DerivationSequence {
+            productions: self.productions(),
+            order,
+            forms: vec![vec![TNT::NT(self.s.clone())]],
+            steps: vec![],
+        }
+    }
+
+    /// Applies the productions in order and returns the derivation tree,
+    /// nonterminals not rewritten yet are leaves.
+    pub fn derivation_tree(&self,
+                           order: DerivationOrder,
+                           steps: &[usize])
+                           -> Result<TNode, DerivationError> {
+        let mut derivation = self.derive(order);
+        for &production in steps {
+            derivation.step(production)?;
+        }
+
+        Ok(derivation.tree())
+    }
+}
+
+impl DerivationSequence {
+    pub fn form(&self) -> &[TNT] {
+        self.forms.last().unwrap()
+    }
+
+    /// Position in the sentential form of the nonterminal the next step rewrites.
+    pub fn next_position(&self) -> Option<usize> {
+        let is_nt = |e: &TNT| matches!(*e, TNT::NT(_));
+        match self.order {
+            DerivationOrder::Leftmost => self.form().iter().position(is_nt),
+            DerivationOrder::Rightmost => self.form().iter().rposition(is_nt),
+        }
+    }
+
+    pub fn next_nonterminal(&self) -> Option<&str> {
+        self.next_position().map(|position| match self.form()[position] {
+                                     TNT::NT(ref nt) => nt.as_str(),
+                                     _ => unreachable!(),
+                                 })
+    }
+
+    pub fn is_sentence(&self) -> bool {
+        self.next_position().is_none()
+    }
+
+    /// Productions that can rewrite the next nonterminal.
+    pub fn choices(&self) -> Vec<usize> {
+        match self.next_nonterminal() {
+            Some(nt) => {
+                (0..self.productions.len()).filter(|&i| self.productions[i].0 == nt).collect()
+            }
+            None => vec![],
+        }
+    }
+
+    pub fn step(&mut self, production: usize) -> Result<&[TNT], DerivationError> {
+        let (ref nt, ref der) = *self.productions
+            .get(production)
+            .ok_or(DerivationError::UnknownProduction(production))?;
+        let position = self.next_position().ok_or(DerivationError::Finished)?;
+        if self.form()[position] != TNT::NT(nt.clone()) {
+            return Err(DerivationError::WrongNonTerminal {
+                           production,
+                           expected: self.next_nonterminal().unwrap().to_string(),
+                       });
+        }
+
+        let form = self.form();
+        let mut next = form[..position].to_vec();
+        next.extend(body(der).iter().cloned());
+        next.extend(form[position + 1..].iter().cloned());
+
+        self.forms.push(next);
+        self.steps.push(production);
+
+        Ok(self.form())
+    }
+
+    /// Derivation tree of the steps so far.
+    pub fn tree(&self) -> TNode {
+        let mut steps = self.steps.iter();
+        let s = self.forms[0][0].clone();
+
+        self.build(s, &mut steps)
+    }
+
+    fn build<'a, I: Iterator<Item = &'a usize>>(&self, val: TNT, steps: &mut I) -> TNode {
+        if !matches!(val, TNT::NT(_)) {
+            return TNode::new(val, vec![]);
+        }
+        let production = match steps.next() {
+            Some(&production) => production,
+            None => return TNode::new(val, vec![]),
+        };
+
+        let der = &self.productions[production].1;
+        let mut children: Vec<TNode> = match self.order {
+            DerivationOrder::Leftmost => {
+                der.iter().map(|e| self.build(e.clone(), steps)).collect()
+            }
+            DerivationOrder::Rightmost => {
+                der.iter().rev().map(|e| self.build(e.clone(), steps)).collect()
+            }
+        };
+        if self.order == DerivationOrder::Rightmost {
+            children.reverse();
+        }
+
+        TNode::new(val, children)
+    }
+}
+
+impl fmt::Display for DerivationSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let forms: Vec<String> = self.forms.iter().map(derivation_to_text).collect();
+        write!(f, "{}", forms.join(" => "))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_text::parse_grammar;
+
+    const EXPR: &str = "E -> E \"+\" T | T ; T -> \"a\" | \"(\" E \")\"";
+
+    fn production(g: &CFG, text: &str) -> usize {
+        g.productions()
+            .iter()
+            .position(|p| format!("{} -> {}", p.0, derivation_to_text(&p.1)) == text)
+            .unwrap()
+    }
+
+    #[test]
+    fn leftmost_steps_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let mut derivation = g.derive(DerivationOrder::Leftmost);
+
+        assert_eq!(derivation.next_nonterminal(), Some("E"));
+        assert_eq!(derivation.choices(), vec![0, 1]);
+
+        derivation.step(production(&g, "E -> E + T")).unwrap();
+        derivation.step(production(&g, "E -> T")).unwrap();
+        assert_eq!(derivation.next_nonterminal(), Some("T"));
+        derivation.step(production(&g, "T -> a")).unwrap();
+        assert!(!derivation.is_sentence());
+        derivation.step(production(&g, "T -> a")).unwrap();
+
+        assert!(derivation.is_sentence());
+        assert_eq!(derivation.to_string(), "E => E + T => T + T => a + T => a + a");
+        assert_eq!(derivation.step(0), Err(DerivationError::Finished));
+    }
+
+    #[test]
+    fn rightmost_steps_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let mut derivation = g.derive(DerivationOrder::Rightmost);
+
+        derivation.step(production(&g, "E -> E + T")).unwrap();
+        assert_eq!(derivation.next_nonterminal(), Some("T"));
+        let err = derivation.step(production(&g, "E -> T")).unwrap_err();
+        assert_eq!(err.to_string(), "production 1 does not rewrite T");
+
+        derivation.step(production(&g, "T -> ( E )")).unwrap();
+        derivation.step(production(&g, "E -> T")).unwrap();
+        derivation.step(production(&g, "T -> a")).unwrap();
+        derivation.step(production(&g, "E -> T")).unwrap();
+        derivation.step(production(&g, "T -> a")).unwrap();
+
+        assert_eq!(derivation.to_string(),
+                   "E => E + T => E + ( E ) => E + ( T ) => E + ( a ) => T + ( a ) => a + ( a )");
+
+        // Both orders build the same tree for the same sentence
+        let leftmost = [0, 1, 2, 3, 1, 2];
+        assert_eq!(derivation.tree(),
+                   g.derivation_tree(DerivationOrder::Leftmost, &leftmost).unwrap());
+        assert_eq!(derivation.step(9), Err(DerivationError::UnknownProduction(9)));
+    }
+
+    #[test]
+    fn derivation_tree_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let tree = g.derivation_tree(DerivationOrder::Leftmost, &[0, 1, 2]).unwrap();
+
+        assert_eq!(tree.to_string(),
+                   "E\n├── E\n│   └── T\n│       └── a\n├── +\n└── T\n");
+
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        let tree = g.derivation_tree(DerivationOrder::Rightmost, &[0, 1, 1]).unwrap();
+        assert_eq!(tree.to_string(), "S\n├── (\n├── S\n│   └── λ\n├── )\n└── S\n    └── λ\n");
+    }
+}
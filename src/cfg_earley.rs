@@ -0,0 +1,442 @@
+// Earley parser for any context free grammar, ambiguous and left
+// recursive ones included.
+//
+// Item sets are built with the Aycock–Horspool fix: predicting a nullable
+// nonterminal also moves the dot over it, so λ productions need no special
+// completion step. The completed items are then turned into a shared packed
+// parse forest where every (nonterminal, start, end) node is stored once
+// along with every way its production can span the input.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cfg::{CFG, TNT, TNode, Derivation, SyntaxError, body};
+
+
+/// Dotted production `productions[production]` with the dot
+/// before the symbol `dot` that started at input position `origin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EarleyItem {
+    pub production: usize,
+    pub dot: usize,
+    pub origin: usize,
+}
+
+/// Parse error at the furthest position the parser reached.
+pub type EarleyError = SyntaxError;
+
+/// Node of the parse forest, nonterminal nodes are identified by the
+/// nonterminal and the span of the input they derive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ForestNode {
+    Terminal(String, usize),
+    Lambda,
+    NonTerminal(String, usize, usize),
+}
+
+/// Shared packed parse forest, every nonterminal node maps to its
+/// families, one list of children for each way it derives its span.
+#[derive(Debug, Clone)]
+pub struct ParseForest {
+    pub root: ForestNode,
+    pub families: BTreeMap<ForestNode, Vec<Vec<ForestNode>>>,
+}
+
+/// Item sets of an input, built by `CFG::earley`.
+#[derive(Debug, Clone)]
+pub struct Earley {
+    pub productions: Vec<(String, Derivation)>,
+    pub symbols: Vec<String>,
+    /// Item set of every input position, sets[i] is built before reading symbols[i]
+    pub sets: Vec<Vec<EarleyItem>>,
+    s: String,
+}
+
+
+impl CFG {
+    /// Runs Earley over the chars of the input, see `earley_symbols`
+    /// for grammars with longer terminals.
+    pub fn earley(&self, input: &str) -> Earley {
+        let symbols: Vec<String> = input.chars().map(|c| c.to_string()).collect();
+        self.earley_symbols(&symbols)
+    }
+
+    pub fn earley_symbols<T: AsRef<str>>(&self, symbols: &[T]) -> Earley {
+        let productions = self.productions();
+        let nullable = self.nullable();
+        let symbols: Vec<String> = symbols.iter().map(|s| s.as_ref().to_string()).collect();
+        let n = symbols.len();
+
+        let mut by_nt: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (index, (nt, _)) in productions.iter().enumerate() {
+            by_nt.entry(nt.as_str()).or_default().push(index);
+        }
+
+        let mut sets: Vec<Vec<EarleyItem>> = vec![vec![]; n + 1];
+        let mut seen: Vec<BTreeSet<EarleyItem>> = vec![BTreeSet::new(); n + 1];
+        {
+            let mut add = |sets: &mut Vec<Vec<EarleyItem>>, i: usize, item: EarleyItem| {
+                if seen[i].insert(item) {
+                    sets[i].push(item);
+                }
+            };
+
+            for &production in by_nt.get(self.s.as_str()).into_iter().flatten() {
+                add(&mut sets,
+                    0,
+                    EarleyItem {
+                        production,
+                        dot: 0,
+                        origin: 0,
+                    });
+            }
+
+            for i in 0..n + 1 {
+                let mut k = 0;
+                while k < sets[i].len() {
+                    let item = sets[i][k];
+                    k += 1;
+
+                    let (ref lhs, ref der) = productions[item.production];
+                    let next = body(der).get(item.dot).cloned();
+                    let advanced = EarleyItem { dot: item.dot + 1, ..item };
+
+                    match next {
+                        Some(TNT::NT(b)) => {
+                            for &production in by_nt.get(b.as_str()).into_iter().flatten() {
+                                add(&mut sets,
+                                    i,
+                                    EarleyItem {
+                                        production,
+                                        dot: 0,
+                                        origin: i,
+                                    });
+                            }
+                            if nullable.contains(&b) {
+                                add(&mut sets, i, advanced);
+                            }
+                        }
+                        Some(TNT::T(t)) => {
+                            if i < n && symbols[i] == t {
+                                add(&mut sets, i + 1, advanced);
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            let waiting: Vec<EarleyItem> = sets[item.origin]
+                                .iter()
+                                .filter(|w| {
+                                    body(&productions[w.production].1).get(w.dot) ==
+                                    Some(&TNT::NT(lhs.clone()))
+                                })
+                                .cloned()
+                                .collect();
+                            for w in waiting {
+                                add(&mut sets, i, EarleyItem { dot: w.dot + 1, ..w });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Earley {
+            productions,
+            symbols,
+            sets,
+            s: self.s.clone(),
+        }
+    }
+}
+
+
+impl Earley {
+    fn is_complete(&self, item: &EarleyItem) -> bool {
+        item.dot == body(&self.productions[item.production].1).len()
+    }
+
+    /// Whether the whole input belongs to the language.
+    pub fn accepted(&self) -> bool {
+        let n = self.symbols.len();
+        self.sets[n].iter().any(|item| {
+            item.origin == 0 && self.productions[item.production].0 == self.s &&
+            self.is_complete(item)
+        })
+    }
+
+    /// The parse forest of the input or the error at the
+    /// furthest position the parser reached.
+    pub fn forest(&self) -> Result<ParseForest, EarleyError> {
+        if !self.accepted() {
+            return Err(self.error());
+        }
+
+        let mut completed = BTreeSet::new();
+        for (end, set) in self.sets.iter().enumerate() {
+            for item in set.iter().filter(|item| self.is_complete(item)) {
+                completed.insert((item.production, item.origin, end));
+            }
+        }
+        let spans: BTreeSet<(&str, usize, usize)> = completed.iter()
+            .map(|&(production, origin, end)| {
+                     (self.productions[production].0.as_str(), origin, end)
+                 })
+            .collect();
+
+        let root = ForestNode::NonTerminal(self.s.clone(), 0, self.symbols.len());
+        let mut families = BTreeMap::new();
+        let mut pending = vec![root.clone()];
+        while let Some(node) = pending.pop() {
+            if families.contains_key(&node) {
+                continue;
+            }
+
+            let (nt, start, end) = match node {
+                ForestNode::NonTerminal(ref nt, start, end) => (nt.clone(), start, end),
+                _ => continue,
+            };
+
+            let mut node_families = vec![];
+            for (production, (lhs, der)) in self.productions.iter().enumerate() {
+                if *lhs != nt || !completed.contains(&(production, start, end)) {
+                    continue;
+                }
+
+                if body(der).is_empty() {
+                    node_families.push(vec![ForestNode::Lambda]);
+                    continue;
+                }
+
+                for family in self.decompose(&spans, body(der), start, end) {
+                    pending.extend(family.iter().cloned());
+                    node_families.push(family);
+                }
+            }
+            families.insert(node, node_families);
+        }
+
+        Ok(ParseForest { root, families })
+    }
+
+    // Every way the symbols can derive the input between start and end
+    fn decompose(&self,
+                 spans: &BTreeSet<(&str, usize, usize)>,
+                 symbols: &[TNT],
+                 start: usize,
+                 end: usize)
+                 -> Vec<Vec<ForestNode>> {
+        let (first, rest) = match symbols.split_first() {
+            Some(split) => split,
+            None => return if start == end { vec![vec![]] } else { vec![] },
+        };
+
+        let mut decompositions = vec![];
+        match *first {
+            TNT::T(ref t) if start < end && self.symbols[start] == *t => {
+                for mut tail in self.decompose(spans, rest, start + 1, end) {
+                    tail.insert(0, ForestNode::Terminal(t.clone(), start));
+                    decompositions.push(tail);
+                }
+            }
+            TNT::NT(ref b) => {
+                for middle in start..end + 1 {
+                    if !spans.contains(&(b.as_str(), start, middle)) {
+                        continue;
+                    }
+                    for mut tail in self.decompose(spans, rest, middle, end) {
+                        tail.insert(0, ForestNode::NonTerminal(b.clone(), start, middle));
+                        decompositions.push(tail);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        decompositions
+    }
+
+    fn error(&self) -> EarleyError {
+        let position = (0..self.sets.len()).rev().find(|&i| !self.sets[i].is_empty()).unwrap_or(0);
+
+        let mut expected = BTreeSet::new();
+        for item in &self.sets[position] {
+            let (ref lhs, ref der) = self.productions[item.production];
+            match body(der).get(item.dot) {
+                Some(TNT::T(t)) => {
+                    expected.insert(TNT::T(t.clone()));
+                }
+                None if item.origin == 0 && *lhs == self.s => {
+                    expected.insert(TNT::Eof);
+                }
+                _ => {}
+            }
+        }
+
+        let found = match self.symbols.get(position) {
+            Some(t) => TNT::T(t.clone()),
+            None => TNT::Eof,
+        };
+
+        EarleyError {
+            position,
+            found,
+            expected,
+        }
+    }
+}
+
+
+impl ParseForest {
+    /// Every derivation tree in the forest. Derivations that go through
+    /// a cycle such as A -> A are skipped since there are infinitely many.
+    pub fn trees(&self) -> Vec<TNode> {
+        self.node_trees(&self.root, &mut vec![])
+    }
+
+    /// The first derivation tree in the forest.
+    pub fn tree(&self) -> Option<TNode> {
+        self.trees().into_iter().next()
+    }
+
+    fn node_trees(&self, node: &ForestNode, path: &mut Vec<ForestNode>) -> Vec<TNode> {
+        let nt = match *node {
+            ForestNode::Terminal(ref t, _) => return vec![TNode::new(TNT::T(t.clone()), vec![])],
+            ForestNode::Lambda => return vec![TNode::new(TNT::Lambda, vec![])],
+            ForestNode::NonTerminal(ref nt, _, _) => nt,
+        };
+
+        if path.contains(node) {
+            return vec![];
+        }
+        path.push(node.clone());
+
+        let mut trees = vec![];
+        for family in &self.families[node] {
+            let mut children_sets: Vec<Vec<TNode>> = vec![vec![]];
+            for child in family {
+                let child_trees = self.node_trees(child, path);
+                children_sets = children_sets.iter()
+                    .flat_map(|children| {
+                                  child_trees.iter().map(move |t| {
+                                                             let mut children = children.clone();
+                                                             children.push(t.clone());
+                                                             children
+                                                         })
+                              })
+                    .collect();
+            }
+
+            for children in children_sets {
+                trees.push(TNode::new(TNT::NT(nt.clone()), children));
+            }
+        }
+
+        path.pop();
+        trees
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::t;
+    use cfg_text::parse_grammar;
+
+    #[test]
+    fn earley_left_recursive_test() {
+        let g = parse_grammar("E -> E \"+\" T | T ; T -> T \"*\" \"a\" | \"a\"").unwrap();
+
+        for &(input, expected) in &[("a", true),
+                                    ("a+a*a", true),
+                                    ("a*a*a+a", true),
+                                    ("a+", false),
+                                    ("+a", false),
+                                    ("", false)] {
+            let earley = g.earley(input);
+            assert_eq!(earley.accepted(), expected, "In {:?}", input);
+
+            if expected {
+                let trees = earley.forest().unwrap().trees();
+                assert_eq!(trees.len(), 1);
+                assert_eq!(trees[0].leaves().concat(), input);
+            }
+        }
+    }
+
+    #[test]
+    fn earley_nullable_test() {
+        // Aycock and Horspool, the nullable B is completed before A is predicted
+        let g = parse_grammar("S -> A A \"x\" ; A -> B ; B -> Lambda").unwrap();
+        let earley = g.earley("x");
+        assert!(earley.accepted());
+
+        let tree = earley.forest().unwrap().tree().unwrap();
+        assert_eq!(tree.children.len(), 3);
+        assert_eq!(tree.children[0].children[0].children,
+                   vec![TNode::new(TNT::Lambda, vec![])]);
+
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        assert!(g.earley("").accepted());
+        assert!(g.earley("(()())").accepted());
+        assert!(!g.earley("(()").accepted());
+    }
+
+    #[test]
+    fn earley_ambiguous_forest_test() {
+        let g = parse_grammar("E -> E \"+\" E | \"a\"").unwrap();
+
+        let forest = g.earley("a+a+a").forest().unwrap();
+        let root = ForestNode::NonTerminal("E".to_string(), 0, 5);
+        assert_eq!(forest.root, root);
+        assert_eq!(forest.families[&root].len(), 2);
+        assert_eq!(forest.trees().len(), 2);
+
+        // The sub forests are shared between both trees
+        let shared = ForestNode::NonTerminal("E".to_string(), 2, 3);
+        assert_eq!(forest.families[&shared], vec![vec![ForestNode::Terminal("a".to_string(), 2)]]);
+
+        assert_eq!(g.earley("a+a+a+a").forest().unwrap().trees().len(), 5);
+    }
+
+    #[test]
+    fn earley_cycle_test() {
+        let g = parse_grammar("S -> S | \"a\"").unwrap();
+        let forest = g.earley("a").forest().unwrap();
+
+        assert_eq!(forest.trees().len(), 1);
+    }
+
+    #[test]
+    fn earley_error_test() {
+        let g = parse_grammar("E -> E \"+\" T | T ; T -> \"(\" E \")\" | \"a\"").unwrap();
+
+        let err = g.earley("(a+a").forest().unwrap_err();
+        assert_eq!(err,
+                   EarleyError {
+                       position: 4,
+                       found: TNT::Eof,
+                       expected: vec![t("+"), t(")")].into_iter().collect(),
+                   });
+
+        let err = g.earley("a+)a").forest().unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.found, t(")"));
+        assert_eq!(err.expected, vec![t("("), t("a")].into_iter().collect());
+        assert_eq!(err.to_string(), "token 2: expected (, a but found )");
+
+        let err = g.earley("a)").forest().unwrap_err();
+        assert_eq!(err.expected, vec![t("+"), TNT::Eof].into_iter().collect());
+    }
+
+    #[test]
+    fn earley_symbols_test() {
+        let g = parse_grammar("Stmt -> \"if\" \"cond\" \"then\" Stmt Else | \"skip\" ;\
+                               Else -> \"else\" Stmt | Lambda")
+            .unwrap();
+
+        let earley = g.earley_symbols(&["if", "cond", "then", "if", "cond", "then", "skip",
+                                        "else", "skip"]);
+        // Dangling else
+        assert_eq!(earley.forest().unwrap().trees().len(), 2);
+    }
+}
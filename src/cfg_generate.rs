@@ -0,0 +1,255 @@
+// Sentences of a grammar, sampled at random or listed exhaustively.
+//
+// The random generator walks the grammar top down and only picks the
+// productions that can still end within the depth bound, the height of
+// the shortest derivation tree of each production being known beforehand.
+// The enumeration computes the sentences of every nonterminal up to the
+// length bound as a least fixpoint, like the generating set.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cfg::{CFG, TNT, Derivation, body};
+use random::Rng;
+
+
+impl CFG {
+    /// Random sentence whose derivation tree is at most `max_depth` levels
+    /// below S, every production being equally likely. None when no
+    /// sentence has such a tree.
+    pub fn generate(&self, rng: &mut Rng, max_depth: usize) -> Option<Vec<String>> {
+        let weights = vec![1; self.productions().len()];
+        self.generate_weighted(rng, max_depth, &weights)
+    }
+
+    /// Like `generate` but each production of `CFG::productions` is chosen
+    /// with a probability proportional to its weight among the productions
+    /// of its nonterminal that fit in the remaining depth. None when there
+    /// is not one weight per production.
+    pub fn generate_weighted(&self,
+                             rng: &mut Rng,
+                             max_depth: usize,
+                             weights: &[u64])
+                             -> Option<Vec<String>> {
+        let productions = self.productions();
+        if weights.len() != productions.len() {
+            return None;
+        }
+        let heights = heights(&productions);
+
+        let mut sentence = vec![];
+        let mut pending = vec![(TNT::NT(self.s.clone()), max_depth)];
+        while let Some((e, depth)) = pending.pop() {
+            let nt = match e {
+                TNT::T(t) => {
+                    sentence.push(t);
+                    continue;
+                }
+                TNT::NT(nt) => nt,
+                _ => continue,
+            };
+
+            let fitting: Vec<u64> = productions.iter()
+                .enumerate()
+                .map(|(i, p)| match heights[i] {
+                         Some(height) if p.0 == nt && height <= depth => weights[i],
+                         _ => 0,
+                     })
+                .collect();
+            let index = rng.weighted(&fitting)?;
+
+            for e in body(&productions[index].1).iter().rev() {
+                pending.push((e.clone(), depth.saturating_sub(1)));
+            }
+        }
+
+        Some(sentence)
+    }
+
+    /// Every sentence of at most `max_len` terminals, shorter ones first
+    /// and then in lexicographic order of their terminals.
+    pub fn enumerate(&self, max_len: usize) -> Vec<Vec<String>> {
+        let mut language: BTreeMap<&String, BTreeSet<Vec<String>>> =
+            self.vn.iter().map(|nt| (nt, BTreeSet::new())).collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nt, dervec) in &self.p {
+                for der in dervec {
+                    let mut sentences: BTreeSet<Vec<String>> = Some(vec![]).into_iter().collect();
+                    for e in body(der) {
+                        let suffixes: BTreeSet<Vec<String>> = match *e {
+                            TNT::NT(ref b) => language[b].clone(),
+                            ref t => Some(vec![terminal(t)]).into_iter().collect(),
+                        };
+                        sentences = sentences.iter()
+                            .flat_map(|s| {
+                                          suffixes.iter().map(move |suffix| [&s[..], suffix].concat())
+                                      })
+                            .filter(|s| s.len() <= max_len)
+                            .collect();
+                    }
+
+                    let nt_sentences = language.get_mut(nt).unwrap();
+                    let len = nt_sentences.len();
+                    nt_sentences.extend(sentences);
+                    changed |= nt_sentences.len() != len;
+                }
+            }
+        }
+
+        let mut sentences: Vec<Vec<String>> = language[&self.s].iter().cloned().collect();
+        sentences.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+        sentences
+    }
+}
+
+
+// Height of the shortest derivation tree of every production, a terminal
+// right side being one level below the nonterminal, None when the
+// production derives no sentence
+fn heights(productions: &[(String, Derivation)]) -> Vec<Option<usize>> {
+    let mut nt_heights: BTreeMap<&String, usize> = BTreeMap::new();
+    let mut heights = vec![None; productions.len()];
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, (nt, der)) in productions.iter().enumerate() {
+            let height = body(der)
+                .iter()
+                .map(|e| match *e {
+                         TNT::NT(ref b) => nt_heights.get(b).map(|h| h + 1),
+                         _ => Some(1),
+                     })
+                .try_fold(1, |max, h| h.map(|h| max.max(h)));
+
+            if let Some(height) = height {
+                if heights[i].is_none_or(|current| height < current) {
+                    heights[i] = Some(height);
+                    changed = true;
+                }
+                let nt_height = nt_heights.entry(nt).or_insert(height);
+                *nt_height = (*nt_height).min(height);
+            }
+        }
+    }
+
+    heights
+}
+
+fn terminal(e: &TNT) -> String {
+    match *e {
+        TNT::T(ref t) => t.clone(),
+        _ => unreachable!(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_text::parse_grammar;
+
+    const PARENS: &str = "S -> \"(\" S \")\" S | Lambda";
+
+    fn balanced(sentence: &[String]) -> bool {
+        let mut depth: i32 = 0;
+        for t in sentence {
+            depth += if t == "(" { 1 } else { -1 };
+            if depth < 0 {
+                return false;
+            }
+        }
+
+        depth == 0
+    }
+
+    fn nesting(sentence: &[String]) -> i32 {
+        let mut depth = 0;
+        let mut max = 0;
+        for t in sentence {
+            depth += if t == "(" { 1 } else { -1 };
+            max = max.max(depth);
+        }
+
+        max
+    }
+
+    #[test]
+    fn enumerate_test() {
+        let g = parse_grammar(PARENS).unwrap();
+        let sentences: Vec<String> = g.enumerate(6).iter().map(|s| s.concat()).collect();
+
+        assert_eq!(sentences,
+                   vec!["", "()", "(())", "()()", "((()))", "(()())", "(())()", "()(())",
+                        "()()()"]);
+
+        let g = parse_grammar("S -> \"a\" S \"b\" | \"ab\"").unwrap();
+        assert_eq!(g.enumerate(2), vec![vec!["ab".to_string()]]);
+        assert_eq!(g.enumerate(3).len(), 2);
+    }
+
+    #[test]
+    fn enumerate_normal_forms_test() {
+        for src in &[PARENS,
+                     "E -> E \"+\" T | T ; T -> T \"*\" F | F ; F -> \"(\" E \")\" | \"a\"",
+                     "S -> A S B | Lambda ; A -> \"a\" A | Lambda ; B -> \"b\""] {
+            let g = parse_grammar(src).unwrap();
+            assert_eq!(g.to_cnf().enumerate(6), g.enumerate(6), "In {:?}", src);
+            assert_eq!(g.to_gnf().enumerate(6), g.enumerate(6), "In {:?}", src);
+        }
+    }
+
+    #[test]
+    fn generate_test() {
+        let g = parse_grammar(PARENS).unwrap();
+        let mut rng = Rng::new(42);
+
+        let mut seen = BTreeSet::new();
+        for _ in 0..200 {
+            let sentence = g.generate(&mut rng, 4).unwrap();
+            assert!(balanced(&sentence), "{:?}", sentence);
+            // Every nested ( takes one more level
+            assert!(nesting(&sentence) <= 3, "{:?}", sentence);
+            seen.insert(sentence);
+        }
+        assert!(seen.len() > 5);
+
+        // Only S -> λ fits in a single level
+        assert_eq!(g.generate(&mut rng, 1), Some(vec![]));
+        assert_eq!(g.generate(&mut rng, 0), None);
+
+        // The same seed generates the same sentences
+        let a: Vec<_> = (0..10).map(|_| g.generate(&mut Rng::new(1), 5)).collect();
+        let b: Vec<_> = (0..10).map(|_| g.generate(&mut Rng::new(1), 5)).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn generate_weighted_test() {
+        let g = parse_grammar("S -> \"a\" S | \"b\" S | Lambda").unwrap();
+        let mut rng = Rng::new(3);
+
+        // Never choose S -> b S
+        let weights = [5, 0, 1];
+        for _ in 0..100 {
+            let sentence = g.generate_weighted(&mut rng, 10, &weights).unwrap();
+            assert!(sentence.iter().all(|t| t == "a"), "{:?}", sentence);
+            assert!(sentence.len() <= 9);
+        }
+
+        // One weight per production is required
+        let g = parse_grammar("S -> \"a\" S | \"b\"").unwrap();
+        assert_eq!(g.generate_weighted(&mut rng, 10, &[1]), None);
+        assert_eq!(g.generate_weighted(&mut rng, 10, &[1, 1, 1]), None);
+
+        // Weights that overflow a u64 sum
+        let g = parse_grammar("S -> \"a\" | \"b\"").unwrap();
+        assert!(g.generate_weighted(&mut rng, 5, &[u64::MAX, 2]).is_some());
+
+        let g = parse_grammar("S -> A ; A -> A \"a\"").unwrap();
+        assert_eq!(g.generate(&mut rng, 10), None);
+    }
+}
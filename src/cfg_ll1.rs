@@ -0,0 +1,288 @@
+// LL(1) predictive parse table, the production A -> α goes into every cell
+// M[A, a] where a belongs to FIRST+(A -> α). The grammar is LL(1) when no
+// cell ends up with more than one production.
+//
+// LL1Parser drives any such table over a stream of terminals and builds
+// the derivation tree as a TNode.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use cfg::{CFG, TNT, TNode, Derivation, SyntaxError, derivation_to_text};
+use cfg_sets::first_plus_with;
+
+
+/// Cells are keyed by (nonterminal, terminal or Eof) and hold
+/// the index of the production in `CFG::productions`.
+pub type LL1Table = BTreeMap<(String, TNT), usize>;
+
+/// A cell of the table claimed by more than one production.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LL1Conflict {
+    pub nt: String,
+    pub terminal: TNT,
+    pub productions: Vec<(usize, Derivation)>,
+}
+
+impl fmt::Display for LL1Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "LL(1) conflict in M[{}, {}] between",
+               self.nt,
+               derivation_to_text(&vec![self.terminal.clone()]))?;
+
+        for (i, &(index, ref der)) in self.productions.iter().enumerate() {
+            let sep = if i == 0 { "" } else { " and" };
+            write!(f, "{} {} -> {} ({})", sep, self.nt, derivation_to_text(der), index)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+impl CFG {
+    /// Builds the LL(1) table or, when the grammar is not LL(1),
+    /// returns every conflicting cell.
+    pub fn ll1_table(&self) -> Result<LL1Table, Vec<LL1Conflict>> {
+        let first = self.first();
+        let follow = self.follow_with(&first);
+
+        let mut cells: BTreeMap<(String, TNT), Vec<usize>> = BTreeMap::new();
+        let productions = self.productions();
+        for (index, (nt, der)) in productions.iter().enumerate() {
+            for a in first_plus_with(&first, &follow, nt, der) {
+                if a == TNT::Lambda {
+                    continue;
+                }
+
+                cells.entry((nt.clone(), a)).or_default().push(index);
+            }
+        }
+
+        let conflicts: Vec<LL1Conflict> = cells.iter()
+            .filter(|&(_, indexes)| indexes.len() > 1)
+            .map(|((nt, terminal), indexes)| {
+                LL1Conflict {
+                    nt: nt.clone(),
+                    terminal: terminal.clone(),
+                    productions: indexes.iter().map(|&i| (i, productions[i].1.clone())).collect(),
+                }
+            })
+            .collect();
+
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+
+        Ok(cells.into_iter().map(|(cell, indexes)| (cell, indexes[0])).collect())
+    }
+}
+
+
+/// Parse error of LL1Parser.
+pub type LL1Error = SyntaxError;
+
+/// Table driven predictive parser for any LL(1) grammar.
+#[derive(Debug, Clone)]
+pub struct LL1Parser {
+    pub productions: Vec<(String, Derivation)>,
+    pub table: LL1Table,
+    pub s: String,
+}
+
+impl LL1Parser {
+    /// The table must have been generated from the same grammar,
+    /// its production indexes refer to `g.productions()`.
+    pub fn new(g: &CFG, table: LL1Table) -> LL1Parser {
+        LL1Parser {
+            productions: g.productions(),
+            table,
+            s: g.s.clone(),
+        }
+    }
+
+    pub fn from_grammar(g: &CFG) -> Result<LL1Parser, Vec<LL1Conflict>> {
+        let table = g.ll1_table()?;
+        Ok(LL1Parser::new(g, table))
+    }
+
+    /// Parses a sequence of terminals, the end of input is implicit.
+    pub fn parse<T: AsRef<str>>(&self, tokens: &[T]) -> Result<TNode, LL1Error> {
+        let derivation = self.leftmost_derivation(tokens)?;
+
+        let mut applied = derivation.iter();
+        Ok(self.tree(&mut applied))
+    }
+
+    /// Indexes of the productions applied by the leftmost derivation of the tokens.
+    pub fn leftmost_derivation<T: AsRef<str>>(&self, tokens: &[T]) -> Result<Vec<usize>, LL1Error> {
+        let mut derivation = vec![];
+        let mut stack = vec![TNT::Eof, TNT::NT(self.s.clone())];
+        let mut index = 0;
+        // Terminals that could have been matched since the last shift,
+        // λ expansions before an error would hide them otherwise
+        let mut expected = BTreeSet::new();
+
+        loop {
+            let token = match tokens.get(index) {
+                Some(t) => TNT::T(t.as_ref().to_string()),
+                None => TNT::Eof,
+            };
+
+            let focus = stack.pop().unwrap();
+            match focus {
+                TNT::Eof if token == TNT::Eof => return Ok(derivation),
+                TNT::T(_) | TNT::Eof => {
+                    if focus != token {
+                        expected.insert(focus);
+                        expected.remove(&token);
+                        return Err(LL1Error {
+                                       position: index,
+                                       found: token,
+                                       expected,
+                                   });
+                    }
+                    index += 1;
+                    expected.clear();
+                }
+                TNT::NT(nt) => {
+                    expected.extend(self.expected(&nt));
+
+                    let cell = (nt, token);
+                    let production = match self.table.get(&cell) {
+                        Some(&production) => production,
+                        None => {
+                            let (_, token) = cell;
+                            expected.remove(&token);
+                            return Err(LL1Error {
+                                           position: index,
+                                           found: token,
+                                           expected,
+                                       });
+                        }
+                    };
+
+                    derivation.push(production);
+                    for e in self.productions[production].1.iter().rev() {
+                        if *e != TNT::Lambda {
+                            stack.push(e.clone());
+                        }
+                    }
+                }
+                TNT::Lambda => {}
+            }
+        }
+    }
+
+    fn expected(&self, nt: &str) -> BTreeSet<TNT> {
+        self.table
+            .keys()
+            .filter(|&(cell_nt, _)| cell_nt == nt)
+            .map(|(_, t)| t.clone())
+            .collect()
+    }
+
+    // Rebuilds the tree from the productions of a leftmost derivation
+    fn tree<'a, I: Iterator<Item = &'a usize>>(&self, applied: &mut I) -> TNode {
+        let (ref nt, ref der) = self.productions[*applied.next().unwrap()];
+
+        let children = der.iter()
+            .map(|e| match *e {
+                     TNT::NT(_) => self.tree(applied),
+                     _ => TNode::new(e.clone(), vec![]),
+                 })
+            .collect();
+
+        TNode::new(TNT::NT(nt.clone()), children)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::t;
+    use cfg_text::parse_grammar;
+
+    #[test]
+    fn ll1_table_test() {
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        let table = g.ll1_table().unwrap();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table[&("S".to_string(), t("("))], 0);
+        assert_eq!(table[&("S".to_string(), t(")"))], 1);
+        assert_eq!(table[&("S".to_string(), TNT::Eof)], 1);
+    }
+
+    #[test]
+    fn ll1_table_conflicts_test() {
+        let g = parse_grammar("S -> \"a\" \"b\" | \"a\" | B ; B -> \"b\" | Lambda").unwrap();
+        let conflicts = g.ll1_table().unwrap_err();
+
+        assert_eq!(conflicts,
+                   vec![LL1Conflict {
+                            nt: "S".to_string(),
+                            terminal: t("a"),
+                            productions: vec![(2, vec![t("a"), t("b")]), (3, vec![t("a")])],
+                        }]);
+        assert_eq!(conflicts[0].to_string(),
+                   "LL(1) conflict in M[S, a] between S -> a b (2) and S -> a (3)");
+    }
+
+    #[test]
+    fn ll1_parser_test() {
+        let g = parse_grammar("E -> T E1 ; E1 -> \"+\" T E1 | Lambda ; T -> \"id\" | \"(\" E \")\"")
+            .unwrap();
+        let parser = LL1Parser::from_grammar(&g).unwrap();
+
+        let tree = parser.parse(&["id", "+", "(", "id", ")"]).unwrap();
+        assert_eq!(tree.val, TNT::NT("E".to_string()));
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].children, vec![TNode::new(t("id"), vec![])]);
+
+        let e1 = &tree.children[1];
+        assert_eq!(e1.children[0].val, t("+"));
+        assert_eq!(e1.children[2].children, vec![TNode::new(TNT::Lambda, vec![])]);
+
+        assert!(parser.parse(&["id"]).is_ok());
+    }
+
+    #[test]
+    fn ll1_parser_errors_test() {
+        let g = parse_grammar("E -> T E1 ; E1 -> \"+\" T E1 | Lambda ; T -> \"id\" | \"(\" E \")\"")
+            .unwrap();
+        let parser = LL1Parser::from_grammar(&g).unwrap();
+
+        let err = parser.parse(&["id", "+"]).unwrap_err();
+        assert_eq!(err,
+                   LL1Error {
+                       position: 2,
+                       found: TNT::Eof,
+                       expected: vec![t("("), t("id")].into_iter().collect(),
+                   });
+        assert_eq!(err.to_string(), "token 2: expected (, id but found eof");
+
+        let err = parser.parse(&["(", "id", "id"]).unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.expected, vec![t("+"), t(")"), TNT::Eof].into_iter().collect());
+
+        let err = parser.parse(&["id", ")"]).unwrap_err();
+        assert_eq!(err.expected, vec![t("+"), TNT::Eof].into_iter().collect());
+
+        let err = parser.parse(&["(", "id"]).unwrap_err();
+        assert_eq!(err.found, TNT::Eof);
+        assert_eq!(err.expected, vec![t("+"), t(")")].into_iter().collect());
+    }
+
+    #[test]
+    fn ll1_table_left_recursion_test() {
+        let g = parse_grammar("E -> E \"+\" \"id\" | \"id\"").unwrap();
+        let conflicts = g.ll1_table().unwrap_err();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].terminal, t("id"));
+        assert_eq!(conflicts[0].productions.len(), 2);
+    }
+}
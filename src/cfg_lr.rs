@@ -0,0 +1,436 @@
+// Bottom up LR parse tables.
+//
+// The grammar is augmented with S' -> S, which takes the index right after
+// the last production of `CFG::productions` so reduce actions refer to
+// the same indexes as the LL(1) table. The canonical LR(0) collection is
+// built with closure and goto and SLR(1) reduces on FOLLOW of the
+// nonterminal.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use automata::{M, StateSet, Delta};
+use cfg::{CFG, TNT, Derivation, body, derivation_to_text};
+use dot;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LRAction {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+}
+
+impl fmt::Display for LRAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LRAction::Shift(state) => write!(f, "s{}", state),
+            LRAction::Reduce(production) => write!(f, "r{}", production),
+            LRAction::Accept => write!(f, "acc"),
+        }
+    }
+}
+
+/// A cell of the ACTION table with more than one action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LRConflict {
+    pub state: usize,
+    pub terminal: TNT,
+    pub actions: Vec<LRAction>,
+}
+
+impl LRConflict {
+    pub fn is_shift_reduce(&self) -> bool {
+        self.actions.iter().any(|a| matches!(*a, LRAction::Shift(_)))
+    }
+}
+
+impl fmt::Display for LRConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_shift_reduce() {
+            "shift/reduce"
+        } else {
+            "reduce/reduce"
+        };
+        let actions: Vec<String> = self.actions.iter().map(|a| a.to_string()).collect();
+
+        write!(f,
+               "{} conflict in state {} on {}: {}",
+               kind,
+               self.state,
+               derivation_to_text(&vec![self.terminal.clone()]),
+               actions.join(", "))
+    }
+}
+
+/// ACTION and GOTO tables. Cells with conflicts hold the action yacc would
+/// pick, shift over reduce and the lowest production among reduces, and
+/// every conflict is listed in `conflicts`.
+#[derive(Debug, Clone)]
+pub struct LRTable {
+    /// Productions of the grammar, the augmented one last
+    pub productions: Vec<(String, Derivation)>,
+    pub action: BTreeMap<(usize, TNT), LRAction>,
+    pub goto: BTreeMap<(usize, String), usize>,
+    pub conflicts: Vec<LRConflict>,
+    states: usize,
+}
+
+impl LRTable {
+    /// Builds the table from every action proposed for each cell
+    /// of an automaton with `states` states.
+    pub fn from_actions(productions: Vec<(String, Derivation)>,
+                        actions: BTreeMap<(usize, TNT), BTreeSet<LRAction>>,
+                        goto: BTreeMap<(usize, String), usize>,
+                        states: usize)
+                        -> LRTable {
+        let mut action = BTreeMap::new();
+        let mut conflicts = vec![];
+        for ((state, terminal), cell) in actions {
+            if cell.len() > 1 {
+                conflicts.push(LRConflict {
+                                   state,
+                                   terminal: terminal.clone(),
+                                   actions: cell.iter().cloned().collect(),
+                               });
+            }
+
+            // Shift sorts first and then the lowest reduce
+            action.insert((state, terminal), *cell.iter().next().unwrap());
+        }
+
+        LRTable {
+            productions,
+            action,
+            goto,
+            conflicts,
+            states,
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LR0Item {
+    pub production: usize,
+    pub dot: usize,
+}
+
+/// Canonical collection of LR(0) item sets, state 0 is the closure of S' -> .S
+#[derive(Debug, Clone)]
+pub struct LR0Automaton {
+    /// Productions of the grammar, the augmented one last
+    pub productions: Vec<(String, Derivation)>,
+    pub states: Vec<BTreeSet<LR0Item>>,
+    pub transitions: BTreeMap<(usize, TNT), usize>,
+}
+
+impl CFG {
+    /// Productions plus S' -> S at the end, S' being a fresh nonterminal.
+    pub fn augmented_productions(&self) -> Vec<(String, Derivation)> {
+        let mut productions = self.productions();
+        productions.push((self.fresh_nonterminal(&self.s), vec![TNT::NT(self.s.clone())]));
+
+        productions
+    }
+
+    pub fn lr0_automaton(&self) -> LR0Automaton {
+        let productions = self.augmented_productions();
+
+        let start = lr0_closure(&productions,
+                                Some(LR0Item {
+                                         production: productions.len() - 1,
+                                         dot: 0,
+                                     })
+                                        .into_iter()
+                                        .collect());
+        let mut states = vec![start.clone()];
+        let mut index: BTreeMap<BTreeSet<LR0Item>, usize> = Some((start, 0)).into_iter().collect();
+        let mut transitions = BTreeMap::new();
+
+        let mut i = 0;
+        while i < states.len() {
+            let mut kernels: BTreeMap<TNT, BTreeSet<LR0Item>> = BTreeMap::new();
+            for item in &states[i] {
+                if let Some(x) = body(&productions[item.production].1).get(item.dot) {
+                    kernels.entry(x.clone()).or_default().insert(LR0Item {
+                                                                       dot: item.dot + 1,
+                                                                       ..*item
+                                                                   });
+                }
+            }
+
+            for (x, kernel) in kernels {
+                let next = lr0_closure(&productions, kernel);
+                let next_index = match index.get(&next) {
+                    Some(&next_index) => next_index,
+                    None => {
+                        states.push(next.clone());
+                        index.insert(next, states.len() - 1);
+                        states.len() - 1
+                    }
+                };
+                transitions.insert((i, x), next_index);
+            }
+            i += 1;
+        }
+
+        LR0Automaton {
+            productions,
+            states,
+            transitions,
+        }
+    }
+
+    /// SLR(1) tables, A -> α. reduces on every terminal of FOLLOW(A).
+    pub fn slr_table(&self) -> LRTable {
+        let automaton = self.lr0_automaton();
+        let follow = self.follow();
+        let augmented = automaton.productions.len() - 1;
+
+        let mut actions: BTreeMap<(usize, TNT), BTreeSet<LRAction>> = BTreeMap::new();
+        let mut goto = BTreeMap::new();
+
+        for (&(state, ref x), &next) in &automaton.transitions {
+            match *x {
+                TNT::T(_) => {
+                    actions.entry((state, x.clone())).or_default().insert(LRAction::Shift(next));
+                }
+                TNT::NT(ref nt) => {
+                    goto.insert((state, nt.clone()), next);
+                }
+                _ => {}
+            }
+        }
+
+        for (state, items) in automaton.states.iter().enumerate() {
+            for item in items {
+                let (ref lhs, ref der) = automaton.productions[item.production];
+                if item.dot < body(der).len() {
+                    continue;
+                }
+
+                if item.production == augmented {
+                    actions.entry((state, TNT::Eof)).or_default().insert(LRAction::Accept);
+                    continue;
+                }
+
+                for a in &follow[lhs] {
+                    actions.entry((state, a.clone()))
+                        .or_default()
+                        .insert(LRAction::Reduce(item.production));
+                }
+            }
+        }
+
+        let states = automaton.states.len();
+        LRTable::from_actions(automaton.productions, actions, goto, states)
+    }
+}
+
+impl LR0Automaton {
+    /// Items of a state, one per line as in `E -> E . + T`.
+    pub fn state_to_string(&self, state: usize) -> String {
+        let items: Vec<String> = self.states[state]
+            .iter()
+            .map(|item| item_to_string(&self.productions[item.production], item.dot))
+            .collect();
+
+        items.join("\n")
+    }
+
+    /// The item automaton as a finite automata over the grammar symbols,
+    /// states are named I0, I1, .. and the final ones hold complete items.
+    pub fn to_automata(&self) -> M<String> {
+        let name = |state: usize| format!("I{}", state);
+
+        let k: StateSet = (0..self.states.len()).map(name).collect();
+        let f: StateSet = (0..self.states.len())
+            .filter(|&state| {
+                        self.states[state].iter().any(|item| {
+                            item.dot == body(&self.productions[item.production].1).len()
+                        })
+                    })
+            .map(name)
+            .collect();
+
+        let mut alphabet = BTreeSet::new();
+        let mut delta: Delta<String> = BTreeSet::new();
+        for (&(state, ref x), &next) in &self.transitions {
+            let symbol = derivation_to_text(&vec![x.clone()]);
+            alphabet.insert(symbol.clone());
+            delta.insert((name(state), symbol, name(next)));
+        }
+
+        M::new(k, alphabet, name(0), f, delta)
+    }
+
+    /// Graphviz DOT drawing of the item automaton.
+    pub fn to_dot(&self) -> String {
+        let mut body = String::new();
+        for state in 0..self.states.len() {
+            let label = format!("I{}\n{}", state, self.state_to_string(state));
+            body.push_str(&dot::state_node(state, &label));
+        }
+        for (&(state, ref x), &next) in &self.transitions {
+            body.push_str(&dot::labeled_edge(state, next, &derivation_to_text(&vec![x.clone()])));
+        }
+
+        dot::digraph("lr0", &body)
+    }
+}
+
+
+pub(crate) fn item_to_string(production: &(String, Derivation), dot: usize) -> String {
+    let symbols = body(&production.1);
+    let mut text: Vec<String> = symbols.iter()
+        .map(|e| derivation_to_text(&vec![e.clone()]))
+        .collect();
+    text.insert(dot, ".".to_string());
+
+    format!("{} -> {}", production.0, text.join(" "))
+}
+
+fn lr0_closure(productions: &[(String, Derivation)],
+               kernel: BTreeSet<LR0Item>)
+               -> BTreeSet<LR0Item> {
+    let mut closure = kernel;
+    let mut pending: Vec<LR0Item> = closure.iter().cloned().collect();
+
+    while let Some(item) = pending.pop() {
+        if let Some(TNT::NT(b)) = body(&productions[item.production].1).get(item.dot) {
+            for (production, (lhs, _)) in productions.iter().enumerate() {
+                let new_item = LR0Item { production, dot: 0 };
+                if lhs == b && closure.insert(new_item) {
+                    pending.push(new_item);
+                }
+            }
+        }
+    }
+
+    closure
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::t;
+    use cfg_text::parse_grammar;
+
+    // Dragon book 4.1
+    const EXPR: &str = "E -> E \"+\" T | T ; T -> T \"*\" F | F ; F -> \"(\" E \")\" | \"id\"";
+
+    #[test]
+    fn lr0_automaton_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let automaton = g.lr0_automaton();
+
+        // Same number of states as the dragon book figure 4.31
+        assert_eq!(automaton.states.len(), 12);
+        assert_eq!(automaton.productions.last().unwrap().0, "E'");
+        assert_eq!(automaton.state_to_string(0),
+                   "E -> . E + T\nE -> . T\nF -> . ( E )\nF -> . id\nT -> . T * F\nT -> . F\n\
+                    E' -> . E");
+
+        let i1 = automaton.transitions[&(0, TNT::NT("E".to_string()))];
+        assert_eq!(automaton.state_to_string(i1), "E -> E . + T\nE' -> E .");
+    }
+
+    #[test]
+    fn lr0_to_automata_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let automaton = g.lr0_automaton();
+        let mut m = automaton.to_automata();
+
+        assert_eq!(m.k.len(), 12);
+        assert_eq!(m.q0, "I0");
+        // Viable prefixes that end in a complete item
+        let symbols = |s: &str| s.split(' ').map(|s| s.to_string()).collect::<Vec<String>>();
+        assert!(m.check_symbols(&symbols("E + T * F")).is_ok());
+        assert!(m.check_symbols(&symbols("( ( id")).is_ok());
+        assert!(m.check_symbols(&symbols("E + T *")).is_err());
+        assert!(m.check_symbols(&symbols("E +")).is_err());
+
+        let dot = automaton.to_dot();
+        assert!(dot.starts_with("digraph lr0 {"));
+        let i1 = automaton.transitions[&(0, TNT::NT("E".to_string()))];
+        assert!(dot.contains(&format!("n0 -> n{} [label=\"E\"];", i1)));
+    }
+
+    #[test]
+    fn slr_table_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let table = g.slr_table();
+        let productions = g.productions();
+        let production = |nt: &str, text: &str| {
+            productions.iter()
+                .position(|p| p.0 == nt && derivation_to_text(&p.1) == text)
+                .unwrap()
+        };
+
+        assert!(table.conflicts.is_empty());
+        assert_eq!(table.states(), 12);
+
+        let i1 = table.goto[&(0, "E".to_string())];
+        assert_eq!(table.action[&(i1, TNT::Eof)], LRAction::Accept);
+        assert!(matches!(table.action[&(i1, t("+"))], LRAction::Shift(_)));
+
+        let i2 = table.goto[&(0, "T".to_string())];
+        let r_e_t = LRAction::Reduce(production("E", "T"));
+        assert_eq!(table.action[&(i2, t("+"))], r_e_t);
+        assert_eq!(table.action[&(i2, t(")"))], r_e_t);
+        assert_eq!(table.action[&(i2, TNT::Eof)], r_e_t);
+        assert!(matches!(table.action[&(i2, t("*"))], LRAction::Shift(_)));
+        assert!(!table.action.contains_key(&(i2, t("id"))));
+    }
+
+    #[test]
+    fn slr_conflicts_test() {
+        let g = parse_grammar("E -> E \"+\" E | \"id\"").unwrap();
+        let table = g.slr_table();
+
+        assert_eq!(table.conflicts.len(), 1);
+        let conflict = &table.conflicts[0];
+        assert!(conflict.is_shift_reduce());
+        assert_eq!(conflict.terminal, t("+"));
+        assert_eq!(table.action[&(conflict.state, t("+"))], conflict.actions[0]);
+        assert_eq!(conflict.to_string(),
+                   format!("shift/reduce conflict in state {} on +: {}, r0",
+                           conflict.state,
+                           conflict.actions[0]));
+
+        // Dragon book 4.48, LR(1) but not SLR
+        let g = parse_grammar("S -> L \"=\" R | R ; L -> \"*\" R | \"id\" ; R -> L").unwrap();
+        let conflicts = g.slr_table().conflicts;
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].terminal, t("="));
+
+        let g = parse_grammar("S -> A \"a\" | B \"a\" ; A -> \"c\" ; B -> \"c\"").unwrap();
+        let conflicts = g.slr_table().conflicts;
+        assert_eq!(conflicts.len(), 1);
+        assert!(!conflicts[0].is_shift_reduce());
+        assert_eq!(conflicts[0].actions, vec![LRAction::Reduce(0), LRAction::Reduce(1)]);
+    }
+
+    #[test]
+    fn slr_lambda_test() {
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        let table = g.slr_table();
+
+        assert!(table.conflicts.is_empty());
+        assert_eq!(table.action[&(0, TNT::Eof)], LRAction::Reduce(1));
+        assert_eq!(table.action[&(0, t("("))], LRAction::Shift(1));
+    }
+
+    #[test]
+    fn lr_table_states_test() {
+        // States without any action or goto are still counted
+        let table = LRTable::from_actions(vec![], BTreeMap::new(), BTreeMap::new(), 3);
+        assert_eq!(table.states(), 3);
+    }
+}
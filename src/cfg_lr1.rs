@@ -0,0 +1,372 @@
+// Canonical LR(1) and LALR(1) parse tables.
+//
+// LR(1) items carry a lookahead terminal and closure propagates
+// FIRST(β a) to the items of B in A -> α . B β, a. LALR(1) merges the
+// LR(1) states that share the same LR(0) core, uniting their lookaheads,
+// so it has as many states as the LR(0) automaton.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use cfg::{CFG, TNT, Derivation, body, derivation_to_text};
+use cfg_sets::{SymbolSetMap, first_of};
+use cfg_lr::{LR0Item, LRAction, LRConflict, LRTable, item_to_string};
+
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LR1Item {
+    pub production: usize,
+    pub dot: usize,
+    pub lookahead: TNT,
+}
+
+impl LR1Item {
+    pub fn core(&self) -> LR0Item {
+        LR0Item {
+            production: self.production,
+            dot: self.dot,
+        }
+    }
+}
+
+/// Collection of LR(1) item sets, state 0 is the closure of [S' -> .S, eof]
+#[derive(Debug, Clone)]
+pub struct LR1Automaton {
+    /// Productions of the grammar, the augmented one last
+    pub productions: Vec<(String, Derivation)>,
+    pub states: Vec<BTreeSet<LR1Item>>,
+    pub transitions: BTreeMap<(usize, TNT), usize>,
+}
+
+impl CFG {
+    pub fn lr1_automaton(&self) -> LR1Automaton {
+        let productions = self.augmented_productions();
+        let first = self.first();
+
+        let start = lr1_closure(&productions,
+                                &first,
+                                Some(LR1Item {
+                                         production: productions.len() - 1,
+                                         dot: 0,
+                                         lookahead: TNT::Eof,
+                                     })
+                                        .into_iter()
+                                        .collect());
+        let mut states = vec![start.clone()];
+        let mut index: BTreeMap<BTreeSet<LR1Item>, usize> = Some((start, 0)).into_iter().collect();
+        let mut transitions = BTreeMap::new();
+
+        let mut i = 0;
+        while i < states.len() {
+            let mut kernels: BTreeMap<TNT, BTreeSet<LR1Item>> = BTreeMap::new();
+            for item in &states[i] {
+                if let Some(x) = body(&productions[item.production].1).get(item.dot) {
+                    kernels.entry(x.clone()).or_default().insert(LR1Item {
+                                                                       dot: item.dot + 1,
+                                                                       ..item.clone()
+                                                                   });
+                }
+            }
+
+            for (x, kernel) in kernels {
+                let next = lr1_closure(&productions, &first, kernel);
+                let next_index = match index.get(&next) {
+                    Some(&next_index) => next_index,
+                    None => {
+                        states.push(next.clone());
+                        index.insert(next, states.len() - 1);
+                        states.len() - 1
+                    }
+                };
+                transitions.insert((i, x), next_index);
+            }
+            i += 1;
+        }
+
+        LR1Automaton {
+            productions,
+            states,
+            transitions,
+        }
+    }
+
+    /// LR(1) automaton with the states of equal core merged, numbered
+    /// in the order their first LR(1) state was found.
+    pub fn lalr_automaton(&self) -> LR1Automaton {
+        let lr1 = self.lr1_automaton();
+
+        let mut cores: BTreeMap<BTreeSet<LR0Item>, usize> = BTreeMap::new();
+        let mut merged: Vec<usize> = vec![];
+        for state in 0..lr1.states.len() {
+            let n = cores.len();
+            merged.push(*cores.entry(lr1.core(state)).or_insert(n));
+        }
+
+        let mut states = vec![BTreeSet::new(); cores.len()];
+        for (state, items) in lr1.states.into_iter().enumerate() {
+            states[merged[state]].extend(items);
+        }
+
+        let transitions = lr1.transitions
+            .into_iter()
+            .map(|((state, x), next)| ((merged[state], x), merged[next]))
+            .collect();
+
+        LR1Automaton {
+            productions: lr1.productions,
+            states,
+            transitions,
+        }
+    }
+
+    /// Canonical LR(1) tables, A -> α. reduces on the lookahead of the item.
+    pub fn lr1_table(&self) -> LRTable {
+        self.lr1_automaton().table()
+    }
+
+    pub fn lalr_table(&self) -> LRTable {
+        self.lalr_automaton().table()
+    }
+
+    /// State counts and conflicts of the SLR(1), LALR(1) and LR(1) tables.
+    pub fn compare_lr(&self) -> LRComparison {
+        let summary = |table: LRTable| {
+            LRSummary {
+                states: table.states(),
+                conflicts: table.conflicts,
+            }
+        };
+
+        LRComparison {
+            slr: summary(self.slr_table()),
+            lalr: summary(self.lalr_table()),
+            lr1: summary(self.lr1_table()),
+        }
+    }
+}
+
+impl LR1Automaton {
+    pub fn core(&self, state: usize) -> BTreeSet<LR0Item> {
+        self.states[state].iter().map(|item| item.core()).collect()
+    }
+
+    /// Items of a state, one per line with the lookaheads of
+    /// the same core joined as in `E -> E . + T, +/eof`.
+    pub fn state_to_string(&self, state: usize) -> String {
+        let mut lookaheads: BTreeMap<LR0Item, Vec<String>> = BTreeMap::new();
+        for item in &self.states[state] {
+            lookaheads.entry(item.core())
+                .or_default()
+                .push(derivation_to_text(&vec![item.lookahead.clone()]));
+        }
+
+        let items: Vec<String> = lookaheads.iter()
+            .map(|(core, lookaheads)| {
+                     format!("{}, {}",
+                             item_to_string(&self.productions[core.production], core.dot),
+                             lookaheads.join("/"))
+                 })
+            .collect();
+
+        items.join("\n")
+    }
+
+    pub fn table(&self) -> LRTable {
+        let augmented = self.productions.len() - 1;
+
+        let mut actions: BTreeMap<(usize, TNT), BTreeSet<LRAction>> = BTreeMap::new();
+        let mut goto = BTreeMap::new();
+
+        for (&(state, ref x), &next) in &self.transitions {
+            match *x {
+                TNT::T(_) => {
+                    actions.entry((state, x.clone())).or_default().insert(LRAction::Shift(next));
+                }
+                TNT::NT(ref nt) => {
+                    goto.insert((state, nt.clone()), next);
+                }
+                _ => {}
+            }
+        }
+
+        for (state, items) in self.states.iter().enumerate() {
+            for item in items {
+                if item.dot < body(&self.productions[item.production].1).len() {
+                    continue;
+                }
+
+                let action = if item.production == augmented {
+                    LRAction::Accept
+                } else {
+                    LRAction::Reduce(item.production)
+                };
+                actions.entry((state, item.lookahead.clone())).or_default().insert(action);
+            }
+        }
+
+        LRTable::from_actions(self.productions.clone(), actions, goto, self.states.len())
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LRSummary {
+    pub states: usize,
+    pub conflicts: Vec<LRConflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LRComparison {
+    pub slr: LRSummary,
+    pub lalr: LRSummary,
+    pub lr1: LRSummary,
+}
+
+impl fmt::Display for LRComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let summaries = [("SLR(1)", &self.slr), ("LALR(1)", &self.lalr), ("LR(1)", &self.lr1)];
+
+        writeln!(f, "{:<8}{:>8}{:>11}", "table", "states", "conflicts")?;
+        for &(name, summary) in &summaries {
+            writeln!(f, "{:<8}{:>8}{:>11}", name, summary.states, summary.conflicts.len())?;
+        }
+        for &(name, summary) in &summaries {
+            for conflict in &summary.conflicts {
+                writeln!(f, "{}: {}", name, conflict)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+fn lr1_closure(productions: &[(String, Derivation)],
+               first: &SymbolSetMap,
+               kernel: BTreeSet<LR1Item>)
+               -> BTreeSet<LR1Item> {
+    let mut closure = kernel;
+    let mut pending: Vec<LR1Item> = closure.iter().cloned().collect();
+
+    while let Some(item) = pending.pop() {
+        let symbols = body(&productions[item.production].1);
+        if let Some(TNT::NT(b)) = symbols.get(item.dot) {
+            let mut beta = symbols[item.dot + 1..].to_vec();
+            beta.push(item.lookahead.clone());
+            let lookaheads = first_of(first, &beta);
+
+            for (production, (lhs, _)) in productions.iter().enumerate() {
+                if lhs != b {
+                    continue;
+                }
+                for a in &lookaheads {
+                    let new_item = LR1Item {
+                        production,
+                        dot: 0,
+                        lookahead: a.clone(),
+                    };
+                    if closure.insert(new_item.clone()) {
+                        pending.push(new_item);
+                    }
+                }
+            }
+        }
+    }
+
+    closure
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::t;
+    use cfg_text::parse_grammar;
+
+    // Dragon book 4.55
+    const CC: &str = "S -> C C ; C -> \"c\" C | \"d\"";
+    // Dragon book 4.48
+    const ASSIGN: &str = "S -> L \"=\" R | R ; L -> \"*\" R | \"id\" ; R -> L";
+    // Dragon book 4.58, LR(1) but not LALR(1)
+    const NOT_LALR: &str = "S -> \"a\" A \"d\" | \"b\" B \"d\" | \"a\" B \"e\" | \"b\" A \"e\" ; \
+                            A -> \"c\" ; B -> \"c\"";
+
+    #[test]
+    fn lr1_automaton_test() {
+        let g = parse_grammar(CC).unwrap();
+        let lr1 = g.lr1_automaton();
+
+        assert_eq!(lr1.states.len(), 10);
+        assert_eq!(lr1.state_to_string(0),
+                   "C -> . c C, c/d\nC -> . d, c/d\nS -> . C C, eof\nS' -> . S, eof");
+
+        let i2 = lr1.transitions[&(0, TNT::NT("C".to_string()))];
+        assert_eq!(lr1.state_to_string(i2),
+                   "C -> . c C, eof\nC -> . d, eof\nS -> C . C, eof");
+    }
+
+    #[test]
+    fn lalr_automaton_test() {
+        let g = parse_grammar(CC).unwrap();
+        let lalr = g.lalr_automaton();
+
+        assert_eq!(lalr.states.len(), 7);
+        assert_eq!(lalr.states.len(), g.lr0_automaton().states.len());
+
+        let i4 = lalr.transitions[&(0, t("d"))];
+        assert_eq!(lalr.state_to_string(i4), "C -> d ., c/d/eof");
+    }
+
+    #[test]
+    fn lr1_table_test() {
+        let g = parse_grammar(ASSIGN).unwrap();
+        assert_eq!(g.slr_table().conflicts.len(), 1);
+
+        let table = g.lr1_table();
+        assert!(table.conflicts.is_empty());
+        assert_eq!(table.states(), 14);
+
+        let lalr = g.lalr_table();
+        assert!(lalr.conflicts.is_empty());
+        assert_eq!(lalr.states(), 10);
+
+        let i2 = lalr.goto[&(0, "L".to_string())];
+        assert!(matches!(lalr.action[&(i2, t("="))], LRAction::Shift(_)));
+        assert!(matches!(lalr.action[&(i2, TNT::Eof)], LRAction::Reduce(_)));
+    }
+
+    #[test]
+    fn not_lalr_test() {
+        let g = parse_grammar(NOT_LALR).unwrap();
+
+        assert!(g.lr1_table().conflicts.is_empty());
+
+        let conflicts = g.lalr_table().conflicts;
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|c| !c.is_shift_reduce()));
+        assert_eq!(conflicts[0].actions, vec![LRAction::Reduce(0), LRAction::Reduce(1)]);
+    }
+
+    #[test]
+    fn compare_lr_test() {
+        let g = parse_grammar(ASSIGN).unwrap();
+        let comparison = g.compare_lr();
+
+        assert_eq!(comparison.slr.states, 10);
+        assert_eq!(comparison.slr.conflicts.len(), 1);
+        assert_eq!(comparison.lalr.states, 10);
+        assert!(comparison.lalr.conflicts.is_empty());
+        assert_eq!(comparison.lr1.states, 14);
+        assert!(comparison.lr1.conflicts.is_empty());
+
+        let report = comparison.to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "table     states  conflicts");
+        assert_eq!(lines[1], "SLR(1)        10          1");
+        assert_eq!(lines[2], "LALR(1)       10          0");
+        assert_eq!(lines[3], "LR(1)         14          0");
+        assert!(lines[4].starts_with("SLR(1): shift/reduce conflict in state"));
+        assert!(lines[4].ends_with("on =: s8, r2"));
+        assert_eq!(lines.len(), 5);
+    }
+}
@@ -0,0 +1,385 @@
+// Shift-reduce driver for the ACTION/GOTO tables of cfg_lr and cfg_lr1.
+//
+// Every shift pushes the value of a terminal and every reduce by A -> α
+// pops the values of α and pushes the one of A computed by a callback,
+// so the reductions come in the reverse order of a rightmost derivation.
+// Without callbacks the values are the TNode trees of each symbol.
+//
+// Panic mode recovery skips the input up to a synchronizing token and
+// pops the stack until a state can go on some nonterminal A to a state
+// with an action for that token, or for the one after it when the
+// synchronizing token ends the erroneous construct. A stands for the
+// skipped input.
+
+use std::collections::BTreeSet;
+
+use cfg::{CFG, TNT, TNode, Derivation, SyntaxError, body};
+use cfg_lr::{LRAction, LRConflict, LRTable};
+
+
+/// Parse error of LRParser.
+pub type LRError = SyntaxError;
+
+/// Outcome of a parse with error recovery, `value` is None
+/// when the parser could not resynchronize before the end of input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LRRecovery<V> {
+    pub value: Option<V>,
+    pub errors: Vec<LRError>,
+}
+
+/// Table driven shift-reduce parser.
+#[derive(Debug, Clone)]
+pub struct LRParser {
+    pub table: LRTable,
+}
+
+impl LRParser {
+    /// Conflicting cells use the action chosen by the table, see `LRTable`.
+    pub fn new(table: LRTable) -> LRParser {
+        LRParser { table }
+    }
+
+    /// Parser over the LALR(1) table of the grammar.
+    pub fn from_grammar(g: &CFG) -> Result<LRParser, Vec<LRConflict>> {
+        let table = g.lalr_table();
+        if !table.conflicts.is_empty() {
+            return Err(table.conflicts);
+        }
+
+        Ok(LRParser::new(table))
+    }
+
+    pub fn productions(&self) -> &[(String, Derivation)] {
+        &self.table.productions
+    }
+
+    /// Parses a sequence of terminals, the end of input is implicit.
+    pub fn parse<T: AsRef<str>>(&self, tokens: &[T]) -> Result<TNode, LRError> {
+        self.parse_with(tokens, leaf, |production, children| self.node(production, children))
+    }
+
+    /// Indexes of the productions in the order they were reduced,
+    /// reversed they are the rightmost derivation of the tokens.
+    pub fn reductions<T: AsRef<str>>(&self, tokens: &[T]) -> Result<Vec<usize>, LRError> {
+        let mut reductions = vec![];
+        self.parse_with(tokens, |_, _| (), |production, _| reductions.push(production))?;
+
+        Ok(reductions)
+    }
+
+    /// Syntax directed translation, `shift` gets the position and the
+    /// terminal of each token and `reduce` the production index and the
+    /// values of its right side.
+    pub fn parse_with<T, V, S, R>(&self,
+                                  tokens: &[T],
+                                  mut shift: S,
+                                  mut reduce: R)
+                                  -> Result<V, LRError>
+        where T: AsRef<str>,
+              S: FnMut(usize, &str) -> V,
+              R: FnMut(usize, Vec<V>) -> V
+    {
+        let recovery = self.run(tokens, None, &mut shift, &mut reduce);
+        match recovery.value {
+            Some(value) => Ok(value),
+            None => Err(recovery.errors.into_iter().next().unwrap()),
+        }
+    }
+
+    /// Parses with panic mode recovery on the `sync` tokens, the
+    /// input skipped by a recovery becomes a childless nonterminal node.
+    pub fn parse_recovering<T: AsRef<str>>(&self,
+                                           tokens: &[T],
+                                           sync: &BTreeSet<TNT>)
+                                           -> LRRecovery<TNode> {
+        self.parse_recovering_with(tokens,
+                                   sync,
+                                   leaf,
+                                   |production, children| self.node(production, children),
+                                   |nt| TNode::new(TNT::NT(nt.to_string()), vec![]))
+    }
+
+    /// Like `parse_with`, `error` gets the nonterminal that
+    /// replaces the skipped input and returns its value.
+    pub fn parse_recovering_with<T, V, S, R, E>(&self,
+                                                tokens: &[T],
+                                                sync: &BTreeSet<TNT>,
+                                                mut shift: S,
+                                                mut reduce: R,
+                                                mut error: E)
+                                                -> LRRecovery<V>
+        where T: AsRef<str>,
+              S: FnMut(usize, &str) -> V,
+              R: FnMut(usize, Vec<V>) -> V,
+              E: FnMut(&str) -> V
+    {
+        self.run(tokens, Some((sync, &mut error)), &mut shift, &mut reduce)
+    }
+
+    // Without recovery the parse stops at the first error
+    fn run<T, V, S, R>(&self,
+                       tokens: &[T],
+                       mut recovery: Option<Recovery<V>>,
+                       shift: &mut S,
+                       reduce: &mut R)
+                       -> LRRecovery<V>
+        where T: AsRef<str>,
+              S: FnMut(usize, &str) -> V,
+              R: FnMut(usize, Vec<V>) -> V
+    {
+        let token_at = |index: usize| match tokens.get(index) {
+            Some(t) => TNT::T(t.as_ref().to_string()),
+            None => TNT::Eof,
+        };
+
+        let mut states = vec![0];
+        let mut values: Vec<V> = vec![];
+        let mut errors = vec![];
+        let mut index = 0;
+        let mut last_recovery = None;
+
+        loop {
+            let token = token_at(index);
+            let state = *states.last().unwrap();
+
+            match self.table.action.get(&(state, token.clone())) {
+                Some(&LRAction::Shift(next)) => {
+                    values.push(shift(index, tokens[index].as_ref()));
+                    states.push(next);
+                    index += 1;
+                }
+                Some(&LRAction::Reduce(production)) => {
+                    let (ref lhs, ref der) = self.table.productions[production];
+                    let n = body(der).len();
+                    states.truncate(states.len() - n);
+                    let children = values.split_off(values.len() - n);
+                    values.push(reduce(production, children));
+
+                    let top = *states.last().unwrap();
+                    states.push(self.table.goto[&(top, lhs.clone())]);
+                }
+                Some(&LRAction::Accept) => {
+                    return LRRecovery {
+                               value: values.pop(),
+                               errors,
+                           }
+                }
+                None => {
+                    errors.push(self.error(state, index, token.clone()));
+                    let (sync, error) = match recovery {
+                        Some((sync, ref mut error)) => (sync, error),
+                        None => return LRRecovery { value: None, errors },
+                    };
+
+                    // The last recovery led to an error on the same
+                    // token, skip it so the parser makes progress
+                    if last_recovery == Some(index) {
+                        if token == TNT::Eof {
+                            return LRRecovery { value: None, errors };
+                        }
+                        index += 1;
+                    }
+
+                    loop {
+                        let token = token_at(index);
+                        if token == TNT::Eof || sync.contains(&token) {
+                            // Resume on the synchronizing token or right after it
+                            let resumed = match self.resume(&states, &token) {
+                                Some(resumed) => Some(resumed),
+                                None if token != TNT::Eof => {
+                                    let resumed = self.resume(&states, &token_at(index + 1));
+                                    if resumed.is_some() {
+                                        index += 1;
+                                    }
+                                    resumed
+                                }
+                                None => None,
+                            };
+
+                            if let Some((depth, nt, next)) = resumed {
+                                states.truncate(depth);
+                                values.truncate(depth - 1);
+                                values.push(error(&nt));
+                                states.push(next);
+                                break;
+                            }
+                            if token == TNT::Eof {
+                                return LRRecovery { value: None, errors };
+                            }
+                        }
+                        index += 1;
+                    }
+                    last_recovery = Some(index);
+                }
+            }
+        }
+    }
+
+    // Deepest stack prefix whose top state goes on a nonterminal
+    // to a state with an action for the token
+    fn resume(&self, states: &[usize], token: &TNT) -> Option<(usize, String, usize)> {
+        for depth in (1..states.len() + 1).rev() {
+            let state = states[depth - 1];
+            for (&(from, ref nt), &next) in &self.table.goto {
+                if from == state && self.table.action.contains_key(&(next, token.clone())) {
+                    return Some((depth, nt.clone(), next));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn error(&self, state: usize, position: usize, found: TNT) -> LRError {
+        let expected = self.table
+            .action
+            .keys()
+            .filter(|&&(from, _)| from == state)
+            .map(|(_, t)| t.clone())
+            .collect();
+
+        LRError {
+            position,
+            found,
+            expected,
+        }
+    }
+
+    fn node(&self, production: usize, children: Vec<TNode>) -> TNode {
+        let nt = TNT::NT(self.table.productions[production].0.clone());
+        if children.is_empty() {
+            return TNode::new(nt, vec![TNode::new(TNT::Lambda, vec![])]);
+        }
+
+        TNode::new(nt, children)
+    }
+}
+
+// Synchronizing tokens and the callback for the skipped input
+type Recovery<'a, V> = (&'a BTreeSet<TNT>, &'a mut dyn FnMut(&str) -> V);
+
+fn leaf(_: usize, token: &str) -> TNode {
+    TNode::new(TNT::T(token.to_string()), vec![])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::{t, derivation_to_text};
+    use cfg_text::parse_grammar;
+
+    const EXPR: &str = "E -> E \"+\" T | T ; T -> T \"*\" F | F ; F -> \"(\" E \")\" | \"num\"";
+
+    #[test]
+    fn lr_parser_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let parser = LRParser::from_grammar(&g).unwrap();
+
+        let tree = parser.parse(&["num", "+", "num", "*", "num"]).unwrap();
+        assert_eq!(tree.val, TNT::NT("E".to_string()));
+        assert_eq!(tree.children.len(), 3);
+        assert_eq!(tree.children[1].val, t("+"));
+        // * binds tighter, the right operand of + is T -> T * F
+        let right = &tree.children[2];
+        assert_eq!(right.val, TNT::NT("T".to_string()));
+        assert_eq!(right.children[1].val, t("*"));
+
+        let tree = LRParser::new(g.slr_table()).parse(&["(", "num", ")"]).unwrap();
+        assert_eq!(tree.children[0].children[0].children[1].val, TNT::NT("E".to_string()));
+    }
+
+    #[test]
+    fn lr_reductions_test() {
+        let g = parse_grammar("S -> \"(\" S \")\" S | Lambda").unwrap();
+        let parser = LRParser::from_grammar(&g).unwrap();
+
+        // S => ( S ) S => ( S ) => ( )
+        assert_eq!(parser.reductions(&["(", ")"]).unwrap(), vec![1, 1, 0]);
+
+        let tree = parser.parse(&["(", ")"]).unwrap();
+        assert_eq!(tree.children[1].children, vec![TNode::new(TNT::Lambda, vec![])]);
+        assert!(parser.parse::<&str>(&[]).is_ok());
+    }
+
+    #[test]
+    fn lr_parse_with_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let parser = LRParser::from_grammar(&g).unwrap();
+
+        let lexemes = ["2", "+", "3", "*", "(", "4", "+", "1", ")"];
+        let tokens: Vec<&str> = lexemes.iter()
+            .map(|l| if l.parse::<i32>().is_ok() { "num" } else { l })
+            .collect();
+
+        let shift = |position: usize, _: &str| lexemes[position].parse::<i32>().unwrap_or(0);
+        let reduce = |production: usize, values: Vec<i32>| {
+            match derivation_to_text(&parser.productions()[production].1).as_str() {
+                "E + T" => values[0] + values[2],
+                "T * F" => values[0] * values[2],
+                "( E )" => values[1],
+                _ => values[0],
+            }
+        };
+
+        assert_eq!(parser.parse_with(&tokens, shift, reduce), Ok(17));
+    }
+
+    #[test]
+    fn lr_parser_errors_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let parser = LRParser::from_grammar(&g).unwrap();
+
+        let err = parser.parse(&["num", "+"]).unwrap_err();
+        assert_eq!(err,
+                   LRError {
+                       position: 2,
+                       found: TNT::Eof,
+                       expected: vec![t("("), t("num")].into_iter().collect(),
+                   });
+        assert_eq!(err.to_string(), "token 2: expected (, num but found eof");
+
+        // LALR merges the lookaheads of F -> num . and may report eof
+        // where the canonical LR(1) table knows only ) can follow
+        let err = parser.parse(&["(", "num", "num"]).unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.expected, vec![t("+"), t("*"), t(")"), TNT::Eof].into_iter().collect());
+
+        let err = LRParser::new(g.lr1_table()).parse(&["(", "num", "num"]).unwrap_err();
+        assert_eq!(err.expected, vec![t("+"), t("*"), t(")")].into_iter().collect());
+    }
+
+    #[test]
+    fn lr_recovery_test() {
+        let g = parse_grammar("P -> P St | St ; St -> \"id\" \"=\" E \";\" ; \
+                               E -> E \"+\" \"id\" | \"id\"")
+            .unwrap();
+        let parser = LRParser::from_grammar(&g).unwrap();
+        let sync: BTreeSet<TNT> = vec![t(";")].into_iter().collect();
+
+        let tokens = ["id", "=", "id", "+", ";", "id", "=", "id", ";"];
+        assert!(parser.parse(&tokens).is_err());
+
+        let recovery = parser.parse_recovering(&tokens, &sync);
+        assert_eq!(recovery.errors.len(), 1);
+        assert_eq!(recovery.errors[0].position, 4);
+
+        let tree = recovery.value.unwrap();
+        assert_eq!(tree.children.len(), 2);
+        // The erroneous expression is replaced by an empty E
+        let first = &tree.children[0].children[0];
+        assert_eq!(first.children[2], TNode::new(TNT::NT("E".to_string()), vec![]));
+
+        let tokens = ["id", "id", ";", "id", "=", "=", ";", "id", "=", "id", ";"];
+        let recovery = parser.parse_recovering(&tokens, &sync);
+        assert_eq!(recovery.errors.iter().map(|e| e.position).collect::<Vec<usize>>(),
+                   vec![1, 5]);
+        assert!(recovery.value.is_some());
+
+        // Nothing to resume on but the end of input, the whole program is lost
+        let recovery = parser.parse_recovering(&["id", "="], &sync);
+        assert_eq!(recovery.errors.len(), 1);
+        assert_eq!(recovery.value, Some(TNode::new(TNT::NT("P".to_string()), vec![])));
+    }
+}
@@ -0,0 +1,632 @@
+// Normalization passes for context free grammars, all of them return
+// a new grammar that generates the same language.
+//
+// The cleanup passes are usually applied in this order: λ productions,
+// unit productions, non generating symbols and unreachable symbols.
+// Normal forms are built on top of them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cfg::{CFG, TNT, Derivation, DerivationVec, ProductionsMap};
+
+
+impl CFG {
+    /// Nonterminals that derive at least one string of terminals.
+    pub fn generating(&self) -> BTreeSet<String> {
+        let mut generating = BTreeSet::new();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nt, dervec) in &self.p {
+                if generating.contains(nt) {
+                    continue;
+                }
+
+                let generates = dervec.iter().any(|der| {
+                    der.iter().all(|e| match *e {
+                                       TNT::NT(ref nt) => generating.contains(nt),
+                                       _ => true,
+                                   })
+                });
+
+                if generates {
+                    generating.insert(nt.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        generating
+    }
+
+    /// Symbols that appear in some sentential form derived from S.
+    pub fn reachable(&self) -> BTreeSet<TNT> {
+        let mut reachable = BTreeSet::new();
+        reachable.insert(TNT::NT(self.s.clone()));
+        let mut pending = vec![self.s.clone()];
+
+        while let Some(nt) = pending.pop() {
+            for der in self.p.get(&nt).into_iter().flatten() {
+                for e in der {
+                    if *e == TNT::Lambda || !reachable.insert(e.clone()) {
+                        continue;
+                    }
+                    if let TNT::NT(ref nt) = *e {
+                        pending.push(nt.clone());
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Removes the nonterminals that do not derive any string of terminals
+    /// along with every production that uses them. S is always kept,
+    /// without productions when the language is empty.
+    pub fn remove_non_generating(&self) -> CFG {
+        let generating = self.generating();
+
+        let mut p = ProductionsMap::new();
+        for (nt, dervec) in &self.p {
+            if !generating.contains(nt) {
+                continue;
+            }
+
+            let kept: DerivationVec = dervec.iter()
+                .filter(|der| {
+                    der.iter().all(|e| match *e {
+                                       TNT::NT(ref nt) => generating.contains(nt),
+                                       _ => true,
+                                   })
+                })
+                .cloned()
+                .collect();
+            p.insert(nt.clone(), kept);
+        }
+
+        let mut vn = generating;
+        vn.insert(self.s.clone());
+        p.entry(self.s.clone()).or_default();
+
+        CFG {
+            vn,
+            vt: self.vt.clone(),
+            p,
+            s: self.s.clone(),
+        }
+    }
+
+    /// Removes the symbols, terminals included, that can not be reached from S.
+    pub fn remove_unreachable(&self) -> CFG {
+        let reachable = self.reachable();
+        let is_reachable = |nt: &String| reachable.contains(&TNT::NT(nt.clone()));
+
+        CFG {
+            vn: self.vn.iter().filter(|&nt| is_reachable(nt)).cloned().collect(),
+            vt: self.vt
+                .iter()
+                .filter(|&t| reachable.contains(&TNT::T(t.clone())))
+                .cloned()
+                .collect(),
+            p: self.p
+                .iter()
+                .filter(|&(nt, _)| is_reachable(nt))
+                .map(|(nt, dervec)| (nt.clone(), dervec.clone()))
+                .collect(),
+            s: self.s.clone(),
+        }
+    }
+
+    /// Removes non generating and then unreachable symbols.
+    pub fn remove_useless(&self) -> CFG {
+        self.remove_non_generating().remove_unreachable()
+    }
+
+    /// Replaces every production by all the variants that omit nullable
+    /// nonterminals and drops the λ productions. S -> λ is kept when the
+    /// language contains the empty string.
+    pub fn remove_lambda_productions(&self) -> CFG {
+        let nullable = self.nullable();
+
+        let mut p = ProductionsMap::new();
+        for (nt, dervec) in &self.p {
+            let mut new_dervec = vec![];
+            for der in dervec {
+                let mut variants: DerivationVec = vec![vec![]];
+                for e in der {
+                    match *e {
+                        TNT::Lambda => {}
+                        TNT::NT(ref b) if nullable.contains(b) => {
+                            let with: DerivationVec = variants.iter()
+                                .map(|v| {
+                                         let mut v = v.clone();
+                                         v.push(e.clone());
+                                         v
+                                     })
+                                .collect();
+                            variants.extend(with);
+                        }
+                        _ => {
+                            for v in &mut variants {
+                                v.push(e.clone());
+                            }
+                        }
+                    }
+                }
+
+                // Variants are generated with the longest last
+                for v in variants.into_iter().rev() {
+                    if !v.is_empty() {
+                        push_unique(&mut new_dervec, v);
+                    }
+                }
+            }
+            p.insert(nt.clone(), new_dervec);
+        }
+
+        if nullable.contains(&self.s) {
+            push_unique(p.entry(self.s.clone()).or_default(), vec![TNT::Lambda]);
+        }
+
+        CFG {
+            vn: self.vn.clone(),
+            vt: self.vt.clone(),
+            p,
+            s: self.s.clone(),
+        }
+    }
+
+    /// Replaces every unit production A -> B by the non unit productions
+    /// of the nonterminals reachable from A through unit productions.
+    pub fn remove_unit_productions(&self) -> CFG {
+        let mut p = ProductionsMap::new();
+        for nt in self.p.keys() {
+            // Unit closure in discovery order so A's own productions come first
+            let mut closure = vec![nt.clone()];
+            let mut i = 0;
+            while i < closure.len() {
+                for der in self.p.get(&closure[i]).into_iter().flatten() {
+                    if let Some(b) = unit(der) {
+                        if !closure.contains(b) {
+                            closure.push(b.clone());
+                        }
+                    }
+                }
+                i += 1;
+            }
+
+            let mut new_dervec = vec![];
+            for b in &closure {
+                for der in self.p.get(b).into_iter().flatten() {
+                    if unit(der).is_none() {
+                        push_unique(&mut new_dervec, der.clone());
+                    }
+                }
+            }
+            p.insert(nt.clone(), new_dervec);
+        }
+
+        CFG {
+            vn: self.vn.clone(),
+            vt: self.vt.clone(),
+            p,
+            s: self.s.clone(),
+        }
+    }
+
+    /// Chomsky Normal Form, every production is A -> B C or A -> a and
+    /// S -> λ is kept when the language contains the empty string. A new
+    /// start symbol is introduced in that case if S shows up in some
+    /// derivation. Long derivations are split with nonterminals named
+    /// A_1, A_2, .. and terminals are lifted into T_a nonterminals.
+    pub fn to_cnf(&self) -> CFG {
+        let mut g = self.clone();
+
+        let s_symbol = TNT::NT(g.s.clone());
+        let s_in_derivations = g.p.values().flatten().flatten().any(|e| *e == s_symbol);
+        if s_in_derivations && g.nullable().contains(&g.s) {
+            let start = g.fresh_nonterminal(&g.s);
+            g.vn.insert(start.clone());
+            g.p.insert(start.clone(), vec![vec![TNT::NT(g.s.clone())]]);
+            g.s = start;
+        }
+
+        let mut g = g.remove_lambda_productions().remove_unit_productions().remove_useless();
+
+        // Terminals inside derivations of length two or more
+        let mut lifted: BTreeMap<String, String> = BTreeMap::new();
+        let mut p = ProductionsMap::new();
+        for (nt, dervec) in g.p.clone() {
+            let mut new_dervec = vec![];
+            for der in dervec {
+                if der.len() < 2 {
+                    new_dervec.push(der);
+                    continue;
+                }
+
+                let der = der.into_iter()
+                    .map(|e| match e {
+                             TNT::T(t) => {
+                                 let lifted_nt = lifted.entry(t.clone())
+                                     .or_insert_with(|| fresh_name(&mut g, &format!("T_{}", t)));
+                                 TNT::NT(lifted_nt.clone())
+                             }
+                             e => e,
+                         })
+                    .collect();
+                new_dervec.push(der);
+            }
+            p.insert(nt, new_dervec);
+        }
+        for (t, nt) in lifted {
+            p.insert(nt, vec![vec![TNT::T(t)]]);
+        }
+
+        // A -> X1 X2 .. Xn becomes A -> X1 A_1, A_1 -> X2 A_2, .., A_n-2 -> Xn-1 Xn
+        let mut split = ProductionsMap::new();
+        for (nt, dervec) in p {
+            let mut index = 0;
+            let mut new_dervec = vec![];
+            for der in dervec {
+                if der.len() <= 2 {
+                    new_dervec.push(der);
+                    continue;
+                }
+
+                let mut lhs = nt.clone();
+                for e in &der[..der.len() - 2] {
+                    let next = loop {
+                        index += 1;
+                        let name = format!("{}_{}", nt, index);
+                        if !g.vn.contains(&name) && !g.vt.contains(&name) {
+                            break name;
+                        }
+                    };
+                    g.vn.insert(next.clone());
+
+                    let body = vec![e.clone(), TNT::NT(next.clone())];
+                    if lhs == nt {
+                        new_dervec.push(body);
+                    } else {
+                        split.insert(lhs, vec![body]);
+                    }
+                    lhs = next;
+                }
+                split.insert(lhs, vec![der[der.len() - 2..].to_vec()]);
+            }
+            split.insert(nt, new_dervec);
+        }
+
+        g.p = split;
+        g
+    }
+
+    /// Greibach Normal Form, every production is A -> a B1 .. Bn and S -> λ
+    /// is kept when the language contains the empty string. Built from the
+    /// CNF by ordering the nonterminals with the start symbol first, making
+    /// every Ai -> Aj γ have j > i while removing direct left recursion with
+    /// primed nonterminals and finally substituting backwards.
+    pub fn to_gnf(&self) -> CFG {
+        let mut g = self.to_cnf();
+
+        let lambda = vec![TNT::Lambda];
+        let s_lambda = g.p[&g.s].contains(&lambda);
+        g.p.get_mut(&g.s).unwrap().retain(|der| *der != lambda);
+
+        let mut order = vec![g.s.clone()];
+        order.extend(g.vn.iter().filter(|&nt| *nt != g.s).cloned());
+
+        let mut primed = vec![];
+        for (i, ai) in order.iter().enumerate() {
+            for aj in &order[..i] {
+                substitute_leading(&mut g, ai, aj);
+            }
+
+            if let Some(b) = remove_direct_recursion_gnf(&mut g, ai) {
+                primed.push(b);
+            }
+        }
+
+        // Every Ai -> Aj γ has now j > i and An starts with terminals
+        for (i, ai) in order.iter().enumerate().rev() {
+            for aj in &order[i + 1..] {
+                substitute_leading(&mut g, ai, aj);
+            }
+        }
+
+        for b in &primed {
+            for aj in &order {
+                substitute_leading(&mut g, b, aj);
+            }
+        }
+
+        if s_lambda {
+            g.p.get_mut(&g.s).unwrap().push(lambda);
+        }
+
+        g.remove_useless()
+    }
+}
+
+
+// Replaces every A -> B γ by A -> δ γ for each derivation δ of B
+fn substitute_leading(g: &mut CFG, a: &str, b: &str) {
+    let b_symbol = TNT::NT(b.to_string());
+    if !g.p[a].iter().any(|der| der[0] == b_symbol) {
+        return;
+    }
+
+    let mut new_dervec = vec![];
+    for der in &g.p[a] {
+        if der[0] != b_symbol {
+            push_unique(&mut new_dervec, der.clone());
+            continue;
+        }
+
+        for delta in &g.p[b] {
+            let mut new_der = delta.clone();
+            new_der.extend(der[1..].iter().cloned());
+            push_unique(&mut new_dervec, new_der);
+        }
+    }
+
+    g.p.insert(a.to_string(), new_dervec);
+}
+
+// A -> A α | β becomes A -> β | β A' and A' -> α | α A', so no λ
+// productions are introduced. Returns A' when there was recursion.
+fn remove_direct_recursion_gnf(g: &mut CFG, a: &str) -> Option<String> {
+    let a_symbol = TNT::NT(a.to_string());
+    let (recursive, rest): (DerivationVec, DerivationVec) =
+        g.p[a].iter().cloned().partition(|der| der[0] == a_symbol);
+
+    if recursive.is_empty() {
+        return None;
+    }
+
+    let fresh = g.fresh_nonterminal(a);
+    g.vn.insert(fresh.clone());
+
+    let mut new_dervec = vec![];
+    for beta in rest {
+        let mut with_fresh = beta.clone();
+        with_fresh.push(TNT::NT(fresh.clone()));
+        push_unique(&mut new_dervec, beta);
+        push_unique(&mut new_dervec, with_fresh);
+    }
+
+    let mut fresh_dervec = vec![];
+    for der in recursive {
+        let alpha = der[1..].to_vec();
+        let mut with_fresh = alpha.clone();
+        with_fresh.push(TNT::NT(fresh.clone()));
+        push_unique(&mut fresh_dervec, alpha);
+        push_unique(&mut fresh_dervec, with_fresh);
+    }
+
+    g.p.insert(a.to_string(), new_dervec);
+    g.p.insert(fresh.clone(), fresh_dervec);
+
+    Some(fresh)
+}
+
+
+// The base name when it is free, primed otherwise
+fn fresh_name(g: &mut CFG, base: &str) -> String {
+    let name = if g.vn.contains(base) || g.vt.contains(base) {
+        g.fresh_nonterminal(base)
+    } else {
+        base.to_string()
+    };
+    g.vn.insert(name.clone());
+
+    name
+}
+
+fn unit(der: &Derivation) -> Option<&String> {
+    match der.as_slice() {
+        [TNT::NT(ref b)] => Some(b),
+        _ => None,
+    }
+}
+
+fn push_unique(dervec: &mut DerivationVec, der: Derivation) {
+    if !dervec.contains(&der) {
+        dervec.push(der);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::derivation_to_text;
+    use cfg_text::parse_grammar;
+
+    fn productions(g: &CFG, nt: &str) -> Vec<String> {
+        g.p[nt].iter().map(derivation_to_text).collect()
+    }
+
+    const GRAMMARS: [&str; 4] = ["S -> A B | \"a\" C ; A -> \"a\" A | Lambda ; B -> \"b\" B | A ; C -> C \"c\"",
+                                 "S -> \"(\" S \")\" S | Lambda",
+                                 "E -> E \"+\" T | T ; T -> T \"*\" F | F ; F -> \"(\" E \")\" | \"a\"",
+                                 "S -> A | \"b\" ; A -> B | S \"a\" ; B -> A | Lambda ; D -> \"d\""];
+
+    #[test]
+    fn remove_non_generating_test() {
+        let g = parse_grammar(GRAMMARS[0]).unwrap();
+        let g2 = g.remove_non_generating();
+
+        assert_eq!(g.generating(), stateset!("S", "A", "B"));
+        assert_eq!(g2.vn, stateset!("S", "A", "B"));
+        assert_eq!(productions(&g2, "S"), vec!["A B"]);
+
+        let g = parse_grammar("S -> \"a\" S").unwrap();
+        let g2 = g.remove_non_generating();
+        assert_eq!(g2.vn, stateset!("S"));
+        assert!(g2.p["S"].is_empty());
+        assert!(g2.enumerate(4).is_empty());
+    }
+
+    #[test]
+    fn remove_unreachable_test() {
+        let g = parse_grammar(GRAMMARS[3]).unwrap();
+        let g2 = g.remove_unreachable();
+
+        assert_eq!(g2.vn, stateset!("S", "A", "B"));
+        assert_eq!(g2.vt, stateset!("a", "b"));
+
+        let g = parse_grammar(GRAMMARS[0]).unwrap().remove_useless();
+        assert_eq!(g.vt, stateset!("a", "b"));
+        assert_eq!(g.vn, stateset!("S", "A", "B"));
+    }
+
+    #[test]
+    fn remove_lambda_productions_test() {
+        let g = parse_grammar(GRAMMARS[1]).unwrap();
+        let g2 = g.remove_lambda_productions();
+
+        assert_eq!(productions(&g2, "S"), vec!["( S ) S", "( ) S", "( S )", "( )", "λ"]);
+
+        let g = parse_grammar("S -> A \"b\" A ; A -> \"a\" | Lambda").unwrap();
+        let g2 = g.remove_lambda_productions();
+        assert_eq!(productions(&g2, "S"), vec!["A b A", "b A", "A b", "b"]);
+        assert_eq!(productions(&g2, "A"), vec!["a"]);
+    }
+
+    #[test]
+    fn remove_unit_productions_test() {
+        let g = parse_grammar(GRAMMARS[2]).unwrap();
+        let g2 = g.remove_unit_productions();
+
+        assert_eq!(productions(&g2, "E"), vec!["E + T", "T * F", "( E )", "a"]);
+        assert_eq!(productions(&g2, "T"), vec!["T * F", "( E )", "a"]);
+        assert_eq!(productions(&g2, "F"), vec!["( E )", "a"]);
+    }
+
+    // S can only show up in derivations when S -> λ is not a production
+    fn is_cnf(g: &CFG) -> bool {
+        let s_lambda = g.p[&g.s].contains(&vec![TNT::Lambda]);
+        g.p.iter().all(|(nt, dervec)| {
+            dervec.iter().all(|der| match der.as_slice() {
+                                  [TNT::T(_)] => true,
+                                  [TNT::NT(ref b), TNT::NT(ref c)] => {
+                                      !s_lambda || (*b != g.s && *c != g.s)
+                                  }
+                                  [TNT::Lambda] => *nt == g.s,
+                                  _ => false,
+                              })
+        })
+    }
+
+    #[test]
+    fn to_cnf_test() {
+        let g = parse_grammar("S -> \"a\" S \"b\" | Lambda").unwrap();
+        let cnf = g.to_cnf();
+
+        assert_eq!(cnf.s, "S'");
+        assert_eq!(productions(&cnf, "S'"), vec!["λ", "T_a S'_1", "T_a T_b"]);
+        assert_eq!(productions(&cnf, "S"), vec!["T_a S_1", "T_a T_b"]);
+        assert_eq!(productions(&cnf, "S'_1"), vec!["S T_b"]);
+        assert_eq!(productions(&cnf, "S_1"), vec!["S T_b"]);
+        assert_eq!(productions(&cnf, "T_a"), vec!["a"]);
+        assert_eq!(productions(&cnf, "T_b"), vec!["b"]);
+        assert!(is_cnf(&cnf));
+    }
+
+    #[test]
+    fn to_cnf_fresh_names_test() {
+        let g = parse_grammar("S -> \"a\" T_a \"a\" S_1 ; T_a -> \"b\" ; S_1 -> \"c\"").unwrap();
+        let cnf = g.to_cnf();
+
+        assert_eq!(cnf.s, "S");
+        assert_eq!(productions(&cnf, "S"), vec!["T_a' S_2"]);
+        assert_eq!(productions(&cnf, "T_a'"), vec!["a"]);
+        assert!(is_cnf(&cnf));
+        assert_eq!(cnf.enumerate(6), g.enumerate(6));
+    }
+
+    #[test]
+    fn to_cnf_preserves_language_test() {
+        for src in GRAMMARS.iter() {
+            let g = parse_grammar(src).unwrap();
+            let cnf = g.to_cnf();
+
+            assert!(is_cnf(&cnf), "{}", cnf);
+            assert_eq!(cnf.enumerate(7), g.enumerate(7), "In {:?}", src);
+            assert!(CFG::check(&cnf.vn, &cnf.vt, &cnf.p, &cnf.s).is_ok());
+        }
+    }
+
+    fn is_gnf(g: &CFG) -> bool {
+        g.p.iter().all(|(nt, dervec)| {
+            dervec.iter().all(|der| match der.split_first() {
+                                  Some((&TNT::T(_), rest)) => {
+                                      rest.iter().all(|e| match *e {
+                                                          TNT::NT(_) => true,
+                                                          _ => false,
+                                                      })
+                                  }
+                                  Some((&TNT::Lambda, _)) => *nt == g.s && der.len() == 1,
+                                  _ => false,
+                              })
+        })
+    }
+
+    #[test]
+    fn to_gnf_test() {
+        let g = parse_grammar("S -> S \"a\" | \"b\"").unwrap();
+        let gnf = g.to_gnf();
+
+        assert_eq!(productions(&gnf, "S"), vec!["b", "b S'"]);
+        assert_eq!(productions(&gnf, "S'"), vec!["a", "a S'"]);
+        assert!(is_gnf(&gnf));
+    }
+
+    #[test]
+    fn to_gnf_preserves_language_test() {
+        let mut grammars = GRAMMARS.to_vec();
+        grammars.push("S -> A B ; A -> B S | \"b\" ; B -> S A | \"a\"");
+
+        for src in grammars {
+            let g = parse_grammar(src).unwrap();
+            let gnf = g.to_gnf();
+
+            assert!(is_gnf(&gnf), "{}", gnf);
+            assert_eq!(gnf.enumerate(7), g.enumerate(7), "In {:?}", src);
+            assert!(CFG::check(&gnf.vn, &gnf.vt, &gnf.p, &gnf.s).is_ok());
+        }
+    }
+
+    #[test]
+    fn cleanup_preserves_language_test() {
+        for src in GRAMMARS.iter() {
+            let g = parse_grammar(src).unwrap();
+            let expected = g.enumerate(6);
+
+            let passes: Vec<(&str, CFG)> =
+                vec![("non generating", g.remove_non_generating()),
+                     ("unreachable", g.remove_unreachable()),
+                     ("useless", g.remove_useless()),
+                     ("lambda", g.remove_lambda_productions()),
+                     ("unit", g.remove_lambda_productions().remove_unit_productions()),
+                     ("all",
+                      g.remove_lambda_productions().remove_unit_productions().remove_useless())];
+
+            for (name, g2) in passes {
+                assert_eq!(g2.enumerate(6), expected, "{} pass on {:?}", name, src);
+                assert!(CFG::check(&g2.vn, &g2.vt, &g2.p, &g2.s).is_ok());
+            }
+
+            let clean = g.remove_lambda_productions().remove_unit_productions().remove_useless();
+            for (nt, dervec) in &clean.p {
+                for der in dervec {
+                    assert!(unit(der).is_none(), "{} -> {:?}", nt, der);
+                    assert!(der != &vec![TNT::Lambda] || *nt == clean.s);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,529 @@
+// Precedence and associativity declarations in the style of yacc.
+//
+// Each call to `left`, `right` or `nonassoc` declares a new level that binds
+// tighter than the previous ones. A production takes the level of its last
+// declared terminal unless `prec` names another one, which also allows
+// levels for operators without a terminal such as concatenation.
+//
+// The declarations resolve the shift/reduce conflicts of an LRTable and
+// drive PrattParser, a precedence climbing parser for expression grammars
+// whose productions only mention the start symbol.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cfg::{CFG, TNT, TNode, Derivation, SyntaxError, derivation_to_text};
+use cfg_lr::{LRAction, LRTable};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Assoc {
+    Left,
+    Right,
+    NonAssoc,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Precedence {
+    /// Level and associativity of every declared name, starting at 1
+    pub levels: BTreeMap<String, (usize, Assoc)>,
+    /// Productions whose level is the one of another name, like %prec
+    pub productions: BTreeMap<usize, String>,
+}
+
+impl Precedence {
+    pub fn new() -> Precedence {
+        Precedence::default()
+    }
+
+    pub fn left(self, names: &[&str]) -> Precedence {
+        self.level(names, Assoc::Left)
+    }
+
+    pub fn right(self, names: &[&str]) -> Precedence {
+        self.level(names, Assoc::Right)
+    }
+
+    pub fn nonassoc(self, names: &[&str]) -> Precedence {
+        self.level(names, Assoc::NonAssoc)
+    }
+
+    /// Gives the production, an index of `CFG::productions`, the level of `name`.
+    pub fn prec(mut self, production: usize, name: &str) -> Precedence {
+        self.productions.insert(production, name.to_string());
+        self
+    }
+
+    fn level(mut self, names: &[&str], assoc: Assoc) -> Precedence {
+        let level = self.levels.values().map(|&(level, _)| level).max().unwrap_or(0) + 1;
+        for name in names {
+            self.levels.insert(name.to_string(), (level, assoc));
+        }
+
+        self
+    }
+
+    pub fn terminal(&self, terminal: &TNT) -> Option<(usize, Assoc)> {
+        match *terminal {
+            TNT::T(ref name) => self.levels.get(name).cloned(),
+            _ => None,
+        }
+    }
+
+    pub fn production(&self,
+                      productions: &[(String, Derivation)],
+                      production: usize)
+                      -> Option<(usize, Assoc)> {
+        if let Some(name) = self.productions.get(&production) {
+            return self.levels.get(name).cloned();
+        }
+
+        productions[production]
+            .1
+            .iter()
+            .rev()
+            .filter_map(|e| self.terminal(e))
+            .next()
+    }
+}
+
+
+impl LRTable {
+    /// Resolves the shift/reduce conflicts whose production and terminal
+    /// have a declared level: the higher level wins and on the same level
+    /// left associativity reduces, right shifts and nonassoc is an error.
+    /// Every other conflict is kept.
+    pub fn resolve(mut self, precedence: &Precedence) -> LRTable {
+        let conflicts = std::mem::take(&mut self.conflicts);
+
+        for conflict in conflicts {
+            let (shift, production) = match conflict.actions.as_slice() {
+                [LRAction::Shift(shift), LRAction::Reduce(production)] => (*shift, *production),
+                _ => {
+                    self.conflicts.push(conflict);
+                    continue;
+                }
+            };
+
+            let levels = (precedence.production(&self.productions, production),
+                          precedence.terminal(&conflict.terminal));
+            let (reduce_level, assoc, shift_level) = match levels {
+                (Some((reduce_level, assoc)), Some((shift_level, _))) => {
+                    (reduce_level, assoc, shift_level)
+                }
+                _ => {
+                    self.conflicts.push(conflict);
+                    continue;
+                }
+            };
+
+            let action = if reduce_level != shift_level {
+                if reduce_level > shift_level {
+                    Some(LRAction::Reduce(production))
+                } else {
+                    Some(LRAction::Shift(shift))
+                }
+            } else {
+                match assoc {
+                    Assoc::Left => Some(LRAction::Reduce(production)),
+                    Assoc::Right => Some(LRAction::Shift(shift)),
+                    Assoc::NonAssoc => None,
+                }
+            };
+
+            let cell = (conflict.state, conflict.terminal);
+            match action {
+                Some(action) => {
+                    self.action.insert(cell, action);
+                }
+                None => {
+                    self.action.remove(&cell);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+
+/// Parse error of PrattParser.
+pub type PrattError = SyntaxError;
+
+/// Precedence climbing parser. Productions that start with a terminal,
+/// like `E -> ( E )` or `E -> - E`, begin an operand and productions
+/// that start with the start symbol, like `E -> E + E`, `E -> E *` or
+/// `E -> E E`, extend the operand on their left.
+#[derive(Debug, Clone)]
+pub struct PrattParser {
+    pub productions: Vec<(String, Derivation)>,
+    pub precedence: Precedence,
+    pub s: String,
+}
+
+impl PrattParser {
+    /// Fails when a production mentions another nonterminal, starts with
+    /// the start symbol without a declared level or is not decidable by
+    /// the next token.
+    pub fn new(g: &CFG, precedence: Precedence) -> Result<PrattParser, String> {
+        let s = TNT::NT(g.s.clone());
+        let productions = g.productions();
+
+        let mut starts = BTreeSet::new();
+        for (nt, der) in &productions {
+            let text = format!("{} -> {}", nt, derivation_to_text(der));
+            if *nt != g.s {
+                return Err(format!("{} is not a production of {}", text, g.s));
+            }
+            if der.is_empty() {
+                return Err(format!("{} -> has an empty right side", nt));
+            }
+            if der.iter().any(|e| *e != s && !matches!(*e, TNT::T(_))) {
+                return Err(format!("{} mentions a symbol other than terminals and {}", text, g.s));
+            }
+
+            let operand = matches!(der[0], TNT::T(_));
+            if operand && !starts.insert(der[0].clone()) {
+                return Err(format!("{} is not the only operand starting with {}",
+                                   text,
+                                   derivation_to_text(&vec![der[0].clone()])));
+            }
+            if !operand && der.len() == 1 {
+                return Err(format!("{} is a unit production", text));
+            }
+        }
+
+        let parser = PrattParser {
+            productions,
+            precedence,
+            s: g.s.clone(),
+        };
+        for (index, (nt, der)) in parser.productions.iter().enumerate() {
+            if der[0] == s && parser.level(index).is_none() {
+                return Err(format!("{} -> {} has no precedence", nt, derivation_to_text(der)));
+            }
+        }
+
+        Ok(parser)
+    }
+
+    /// Parses a sequence of terminals, the end of input is implicit.
+    pub fn parse<T: AsRef<str>>(&self, tokens: &[T]) -> Result<TNode, PrattError> {
+        let tokens: Vec<TNT> = tokens.iter().map(|t| TNT::T(t.as_ref().to_string())).collect();
+        let mut index = 0;
+
+        let tree = self.expr(&tokens, &mut index, 0)?;
+        if index < tokens.len() {
+            let mut expected = self.infix();
+            expected.insert(TNT::Eof);
+            return Err(self.error(&tokens, index, expected));
+        }
+
+        Ok(tree)
+    }
+
+    fn expr(&self, tokens: &[TNT], index: &mut usize, min: usize) -> Result<TNode, PrattError> {
+        let token = token_at(tokens, *index);
+        let prefix = self.productions
+            .iter()
+            .position(|p| p.1[0] == token)
+            .ok_or_else(|| self.error(tokens, *index, self.prefix()))?;
+        *index += 1;
+
+        let level = self.level(prefix).map_or(0, |(level, _)| level);
+        let mut left = self.rest(tokens, index, prefix, vec![TNode::new(token, vec![])], level)?;
+
+        loop {
+            let token = token_at(tokens, *index);
+            let infix = match self.infix_production(&token) {
+                Some(infix) => infix,
+                None => break,
+            };
+            let (level, assoc) = self.level(infix).unwrap();
+            if level < min {
+                break;
+            }
+
+            let right = match assoc {
+                Assoc::Right => level,
+                _ => level + 1,
+            };
+            left = self.rest(tokens, index, infix, vec![left], right)?;
+
+            if assoc == Assoc::NonAssoc {
+                let token = token_at(tokens, *index);
+                if let Some(next) = self.infix_production(&token) {
+                    if self.level(next) == Some((level, assoc)) {
+                        let mut expected = self.infix();
+                        expected.insert(TNT::Eof);
+                        expected.remove(&token);
+                        return Err(self.error(tokens, *index, expected));
+                    }
+                }
+            }
+        }
+
+        Ok(left)
+    }
+
+    // Matches the symbols of the production after the first `children`,
+    // a trailing start symbol binds with `right` and the others with 0
+    fn rest(&self,
+            tokens: &[TNT],
+            index: &mut usize,
+            production: usize,
+            mut children: Vec<TNode>,
+            right: usize)
+            -> Result<TNode, PrattError> {
+        let (ref nt, ref der) = self.productions[production];
+
+        for (i, e) in der.iter().enumerate().skip(children.len()) {
+            match *e {
+                TNT::NT(_) => {
+                    let min = if i == der.len() - 1 { right } else { 0 };
+                    children.push(self.expr(tokens, index, min)?);
+                }
+                _ => {
+                    let token = token_at(tokens, *index);
+                    if token != *e {
+                        let expected = Some(e.clone()).into_iter().collect();
+                        return Err(self.error(tokens, *index, expected));
+                    }
+                    children.push(TNode::new(token, vec![]));
+                    *index += 1;
+                }
+            }
+        }
+
+        Ok(TNode::new(TNT::NT(nt.clone()), children))
+    }
+
+    // Production extending the left operand on the token, an operator
+    // before juxtaposition with an operand that starts with it
+    fn infix_production(&self, token: &TNT) -> Option<usize> {
+        let s = TNT::NT(self.s.clone());
+        let operator = self.productions
+            .iter()
+            .position(|p| p.1[0] == s && p.1.get(1) == Some(token));
+
+        operator.or_else(|| if self.prefix().contains(token) {
+                             self.productions.iter().position(|p| p.1 == [s.clone(), s.clone()])
+                         } else {
+                             None
+                         })
+    }
+
+    fn level(&self, production: usize) -> Option<(usize, Assoc)> {
+        self.precedence.production(&self.productions, production)
+    }
+
+    fn prefix(&self) -> BTreeSet<TNT> {
+        self.productions
+            .iter()
+            .filter(|p| matches!(p.1[0], TNT::T(_)))
+            .map(|p| p.1[0].clone())
+            .collect()
+    }
+
+    fn infix(&self) -> BTreeSet<TNT> {
+        let s = TNT::NT(self.s.clone());
+        let mut infix: BTreeSet<TNT> = self.productions
+            .iter()
+            .filter(|p| p.1[0] == s && p.1.len() > 1 && p.1[1] != s)
+            .map(|p| p.1[1].clone())
+            .collect();
+        if self.productions.iter().any(|p| p.1 == [s.clone(), s.clone()]) {
+            infix.extend(self.prefix());
+        }
+
+        infix
+    }
+
+    fn error(&self, tokens: &[TNT], position: usize, expected: BTreeSet<TNT>) -> PrattError {
+        PrattError {
+            position,
+            found: token_at(tokens, position),
+            expected,
+        }
+    }
+}
+
+fn token_at(tokens: &[TNT], index: usize) -> TNT {
+    tokens.get(index).cloned().unwrap_or(TNT::Eof)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_lr_parser::LRParser;
+    use cfg_text::parse_grammar;
+
+    const EXPR: &str = "E -> E \"+\" E | E \"*\" E | E \"^\" E | E \"<\" E | \
+                        \"-\" E | \"(\" E \")\" | \"n\"";
+    const REGEX: &str = "R -> R \"|\" R | R R | R \"*\" | \"(\" R \")\" | \"a\" | \"b\"";
+
+    fn production(g: &CFG, text: &str) -> usize {
+        g.productions()
+            .iter()
+            .position(|p| format!("{} -> {}", p.0, derivation_to_text(&p.1)) == text)
+            .unwrap()
+    }
+
+    fn expr_precedence(g: &CFG) -> Precedence {
+        Precedence::new()
+            .nonassoc(&["<"])
+            .left(&["+"])
+            .left(&["*"])
+            .right(&["^"])
+            .right(&["UMINUS"])
+            .prec(production(g, "E -> - E"), "UMINUS")
+    }
+
+    fn regex_precedence(g: &CFG) -> Precedence {
+        Precedence::new()
+            .left(&["|"])
+            .left(&["CONCAT", "(", "a", "b"])
+            .left(&["*"])
+            .prec(production(g, "R -> R R"), "CONCAT")
+    }
+
+    fn tokens(s: &str) -> Vec<&str> {
+        s.split(' ').collect()
+    }
+
+    #[test]
+    fn precedence_levels_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let precedence = expr_precedence(&g);
+        let productions = g.productions();
+
+        assert_eq!(precedence.terminal(&TNT::T("+".to_string())), Some((2, Assoc::Left)));
+        assert_eq!(precedence.terminal(&TNT::T("n".to_string())), None);
+        assert_eq!(precedence.production(&productions, production(&g, "E -> E ^ E")),
+                   Some((4, Assoc::Right)));
+        assert_eq!(precedence.production(&productions, production(&g, "E -> - E")),
+                   Some((5, Assoc::Right)));
+        assert_eq!(precedence.production(&productions, production(&g, "E -> n")), None);
+    }
+
+    #[test]
+    fn resolve_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let table = g.lalr_table();
+        assert!(!table.conflicts.is_empty());
+        assert!(table.conflicts.iter().all(|c| c.is_shift_reduce()));
+
+        let table = table.resolve(&expr_precedence(&g));
+        assert!(table.conflicts.is_empty());
+
+        let parser = LRParser::new(table);
+        let plus = TNT::T("+".to_string());
+        let star = TNT::T("*".to_string());
+
+        let tree = parser.parse(&tokens("n + n * n")).unwrap();
+        assert_eq!(tree.children[1].val, plus);
+        assert_eq!(tree.children[2].children[1].val, star);
+
+        let tree = parser.parse(&tokens("n + n + n")).unwrap();
+        assert_eq!(tree.children[0].children[1].val, plus);
+
+        let tree = parser.parse(&tokens("n ^ n ^ n")).unwrap();
+        assert_eq!(tree.children[2].children[1].val, TNT::T("^".to_string()));
+
+        let tree = parser.parse(&tokens("- n * n")).unwrap();
+        assert_eq!(tree.children[1].val, star);
+
+        assert!(parser.parse(&tokens("n < n + n")).is_ok());
+        let err = parser.parse(&tokens("n < n < n")).unwrap_err();
+        assert_eq!(err.position, 3);
+    }
+
+    #[test]
+    fn resolve_keeps_undeclared_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let plus = TNT::T("+".to_string());
+        let reduce_plus = LRAction::Reduce(production(&g, "E -> E + E"));
+        let conflicts = g.lalr_table().resolve(&Precedence::new().left(&["+"])).conflicts;
+
+        assert!(!conflicts.is_empty());
+        assert!(conflicts.iter().all(|c| c.terminal != plus || !c.actions.contains(&reduce_plus)));
+    }
+
+    #[test]
+    fn pratt_parser_test() {
+        let g = parse_grammar(EXPR).unwrap();
+        let precedence = expr_precedence(&g);
+        let lr = LRParser::new(g.lalr_table().resolve(&precedence));
+        let pratt = PrattParser::new(&g, precedence).unwrap();
+
+        for input in &["n",
+                       "n + n * n",
+                       "n * n + n",
+                       "n + n + n",
+                       "n ^ n ^ n",
+                       "- n ^ n * n",
+                       "( n + n ) * - n",
+                       "n < n + n",
+                       "n + n < n * n"] {
+            assert_eq!(pratt.parse(&tokens(input)),
+                       Ok(lr.parse(&tokens(input)).unwrap()),
+                       "In {}",
+                       input);
+        }
+
+        let err = pratt.parse(&tokens("n < n < n")).unwrap_err();
+        assert_eq!(err.position, 3);
+
+        let err = pratt.parse(&tokens("n +")).unwrap_err();
+        assert_eq!(err.to_string(), "token 2: expected (, -, n but found eof");
+
+        let err = pratt.parse(&tokens("( n n")).unwrap_err();
+        assert_eq!(err.to_string(), "token 2: expected ) but found n");
+
+        let err = pratt.parse(&tokens("n )")).unwrap_err();
+        assert_eq!(err.position, 1);
+        assert!(err.expected.contains(&TNT::Eof));
+    }
+
+    #[test]
+    fn pratt_regex_test() {
+        let g = parse_grammar(REGEX).unwrap();
+        let precedence = regex_precedence(&g);
+        let table = g.lalr_table().resolve(&precedence);
+        assert!(table.conflicts.is_empty());
+
+        let lr = LRParser::new(table);
+        let pratt = PrattParser::new(&g, precedence).unwrap();
+
+        for input in &["a", "a b", "a | b a *", "( a | b ) * a", "a b | b a", "a * * b"] {
+            assert_eq!(pratt.parse(&tokens(input)),
+                       Ok(lr.parse(&tokens(input)).unwrap()),
+                       "In {}",
+                       input);
+        }
+
+        // union < concatenation < star
+        let tree = pratt.parse(&tokens("a | b a *")).unwrap();
+        assert_eq!(tree.children[1].val, TNT::T("|".to_string()));
+        let concat = &tree.children[2];
+        assert_eq!(concat.children.len(), 2);
+        assert_eq!(concat.children[1].children[1].val, TNT::T("*".to_string()));
+    }
+
+    #[test]
+    fn pratt_parser_new_errors_test() {
+        let g = parse_grammar("E -> E \"+\" T | T ; T -> \"n\"").unwrap();
+        assert_eq!(PrattParser::new(&g, Precedence::new()).unwrap_err(),
+                   "E -> E + T mentions a symbol other than terminals and E");
+
+        let g = parse_grammar(EXPR).unwrap();
+        assert_eq!(PrattParser::new(&g, Precedence::new().left(&["+"])).unwrap_err(),
+                   "E -> E * E has no precedence");
+
+        // An empty derivation passes CFG::check
+        let mut g = parse_grammar("E -> \"n\"").unwrap();
+        g.p.get_mut("E").unwrap().push(vec![]);
+        assert_eq!(PrattParser::new(&g, Precedence::new()).unwrap_err(),
+                   "E -> has an empty right side");
+    }
+}
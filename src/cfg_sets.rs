@@ -0,0 +1,273 @@
+// Nullable, FIRST, FOLLOW and FIRST+ sets of context free grammars,
+// all of them are computed by fixed point iteration.
+//
+// FIRST sets hold terminals and Lambda when the symbols can derive the
+// empty string, FOLLOW sets hold terminals and Eof when the nonterminal
+// can appear at the end of a sentential form.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cfg::{CFG, TNT};
+
+
+pub type SymbolSet = BTreeSet<TNT>;
+pub type SymbolSetMap = BTreeMap<String, SymbolSet>;
+
+
+impl CFG {
+    /// Nonterminals that derive the empty string.
+    pub fn nullable(&self) -> BTreeSet<String> {
+        let mut nullable = BTreeSet::new();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nt, dervec) in &self.p {
+                if nullable.contains(nt) {
+                    continue;
+                }
+
+                let derives_lambda = dervec.iter().any(|der| {
+                    der.iter().all(|e| match *e {
+                                       TNT::Lambda => true,
+                                       TNT::NT(ref nt) => nullable.contains(nt),
+                                       _ => false,
+                                   })
+                });
+
+                if derives_lambda {
+                    nullable.insert(nt.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        nullable
+    }
+
+    /// FIRST set of every nonterminal.
+    pub fn first(&self) -> SymbolSetMap {
+        let mut first: SymbolSetMap = self.vn
+            .iter()
+            .map(|nt| (nt.clone(), SymbolSet::new()))
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nt, dervec) in &self.p {
+                for der in dervec {
+                    let der_first = first_of(&first, der);
+                    let nt_first = first.get_mut(nt).unwrap();
+                    let len = nt_first.len();
+                    nt_first.extend(der_first);
+                    changed |= nt_first.len() != len;
+                }
+            }
+        }
+
+        first
+    }
+
+    /// FOLLOW set of every nonterminal, Eof follows the start symbol.
+    pub fn follow(&self) -> SymbolSetMap {
+        let first = self.first();
+        self.follow_with(&first)
+    }
+
+    pub(crate) fn follow_with(&self, first: &SymbolSetMap) -> SymbolSetMap {
+        let mut follow: SymbolSetMap = self.vn
+            .iter()
+            .map(|nt| (nt.clone(), SymbolSet::new()))
+            .collect();
+        follow.get_mut(&self.s).unwrap().insert(TNT::Eof);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nt, dervec) in &self.p {
+                for der in dervec {
+                    for (i, e) in der.iter().enumerate() {
+                        let b = match *e {
+                            TNT::NT(ref b) => b,
+                            _ => continue,
+                        };
+
+                        let mut rest_first = first_of(first, &der[i + 1..]);
+                        if rest_first.remove(&TNT::Lambda) {
+                            rest_first.extend(follow[nt].iter().cloned());
+                        }
+
+                        let b_follow = follow.get_mut(b).unwrap();
+                        let len = b_follow.len();
+                        b_follow.extend(rest_first);
+                        changed |= b_follow.len() != len;
+                    }
+                }
+            }
+        }
+
+        follow
+    }
+
+    /// FIRST+ set of the production with the given index in `productions()`,
+    /// that is FIRST of the derivation plus FOLLOW of the nonterminal
+    /// when the derivation can be empty. None when there is no such
+    /// production. FIRST and FOLLOW are computed on every call, compute
+    /// them once and use `first_plus_with` to go over every production.
+    pub fn first_plus(&self, production: usize) -> Option<SymbolSet> {
+        let (nt, der) = self.p
+            .iter()
+            .flat_map(|(nt, dervec)| dervec.iter().map(move |der| (nt, der)))
+            .nth(production)?;
+        let first = self.first();
+        let follow = self.follow_with(&first);
+
+        Some(first_plus_with(&first, &follow, nt, der))
+    }
+}
+
+
+/// FIRST set of a sequence of symbols given the FIRST sets of the nonterminals,
+/// it contains Lambda when every symbol of the sequence can derive the empty string.
+pub fn first_of(first: &SymbolSetMap, symbols: &[TNT]) -> SymbolSet {
+    let mut set = SymbolSet::new();
+    for e in symbols {
+        match *e {
+            TNT::T(_) | TNT::Eof => {
+                set.insert(e.clone());
+                return set;
+            }
+            TNT::NT(ref nt) => {
+                let nt_first = &first[nt];
+                set.extend(nt_first.iter().filter(|&e| *e != TNT::Lambda).cloned());
+                if !nt_first.contains(&TNT::Lambda) {
+                    return set;
+                }
+            }
+            TNT::Lambda => {}
+        }
+    }
+
+    set.insert(TNT::Lambda);
+    set
+}
+
+/// FIRST+ set of the derivation `der` of `nt` given the FIRST and FOLLOW
+/// sets of the grammar.
+pub fn first_plus_with(first: &SymbolSetMap,
+                       follow: &SymbolSetMap,
+                       nt: &str,
+                       der: &[TNT])
+                       -> SymbolSet {
+    let mut set = first_of(first, der);
+    if set.contains(&TNT::Lambda) {
+        set.extend(follow[nt].iter().cloned());
+    }
+
+    set
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_text::parse_grammar;
+
+    fn set(symbols: &[&str]) -> SymbolSet {
+        symbols.iter()
+            .map(|s| match *s {
+                     "Lambda" => TNT::Lambda,
+                     "eof" => TNT::Eof,
+                     s => TNT::T(s.to_string()),
+                 })
+            .collect()
+    }
+
+    fn regexp_grammar() -> CFG {
+        let md = include_str!("../grammar.md");
+        let start = md.find("```\n").unwrap() + 4;
+        let end = start + md[start..].find("```").unwrap();
+
+        parse_grammar(&md[start..end]).unwrap()
+    }
+
+    #[test]
+    fn nullable_test() {
+        let g = regexp_grammar();
+        assert_eq!(g.nullable(), stateset!("Ops", "ReL"));
+
+        let g = parse_grammar("S -> A B ; A -> \"a\" | Lambda ; B -> A A ; C -> \"c\" C").unwrap();
+        assert_eq!(g.nullable(), stateset!("S", "A", "B"));
+    }
+
+    // First and Follow calc table in grammar.md
+    #[test]
+    fn first_follow_test() {
+        let g = regexp_grammar();
+
+        let first = g.first();
+        assert_eq!(first["Re"], set(&["Lit", "("]));
+        assert_eq!(first["Ops"], set(&["*", "+", "|", "Lit", "(", "Lambda"]));
+        assert_eq!(first["ReL"], set(&["Lit", "(", "Lambda"]));
+
+        let follow = g.follow();
+        assert_eq!(follow["Re"], set(&["eof", ")"]));
+        assert_eq!(follow["Ops"], set(&["eof", ")"]));
+        assert_eq!(follow["ReL"], set(&["eof", ")"]));
+    }
+
+    // First+ table in grammar.md
+    #[test]
+    fn first_plus_test() {
+        let g = regexp_grammar();
+        let productions = g.productions();
+        let first_plus = |nt: &str, der: &[&str]| {
+            let index = productions.iter()
+                .position(|&(ref p_nt, ref p_der)| {
+                              p_nt == nt &&
+                              p_der.iter().map(|e| match *e {
+                                                   TNT::T(ref s) | TNT::NT(ref s) => s.as_str(),
+                                                   _ => "Lambda",
+                                               })
+                                  .eq(der.iter().cloned())
+                          })
+                .unwrap();
+            g.first_plus(index).unwrap()
+        };
+
+        assert_eq!(first_plus("Re", &["Lit", "Ops"]), set(&["Lit"]));
+        assert_eq!(first_plus("Re", &["(", "Re", ")", "Ops"]), set(&["("]));
+        assert_eq!(first_plus("Ops", &["*", "ReL"]), set(&["*"]));
+        assert_eq!(first_plus("Ops", &["+", "ReL"]), set(&["+"]));
+        assert_eq!(first_plus("Ops", &["|", "Re"]), set(&["|"]));
+        assert_eq!(first_plus("Ops", &["Re"]), set(&["Lit", "("]));
+        assert_eq!(first_plus("Ops", &["Lambda"]), set(&["eof", ")", "Lambda"]));
+        assert_eq!(first_plus("ReL", &["Re"]), set(&["Lit", "("]));
+        assert_eq!(first_plus("ReL", &["Lambda"]), set(&["eof", ")", "Lambda"]));
+        assert_eq!(g.first_plus(productions.len()), None);
+
+        // The same sets computing FIRST and FOLLOW once
+        let first = g.first();
+        let follow = g.follow();
+        for (i, (nt, der)) in productions.iter().enumerate() {
+            assert_eq!(Some(first_plus_with(&first, &follow, nt, der)), g.first_plus(i));
+        }
+    }
+
+    #[test]
+    fn first_of_nullable_sequence_test() {
+        let g = parse_grammar("S -> A B \"c\" ; A -> \"a\" | Lambda ; B -> \"b\" | Lambda").unwrap();
+        let first = g.first();
+
+        assert_eq!(first["S"], set(&["a", "b", "c"]));
+        assert_eq!(first_of(&first, &[TNT::NT("A".to_string()), TNT::NT("B".to_string())]),
+                   set(&["a", "b", "Lambda"]));
+        assert_eq!(first_of(&first, &[]), set(&["Lambda"]));
+
+        let follow = g.follow();
+        assert_eq!(follow["S"], set(&["eof"]));
+        assert_eq!(follow["A"], set(&["b", "c"]));
+        assert_eq!(follow["B"], set(&["c"]));
+    }
+}
@@ -0,0 +1,630 @@
+// BNF / EBNF text definition of context free grammars
+//
+//     # comments run until the end of the line
+//     Expr -> Expr "+" Term | Term ;
+//     Term -> "(" Expr ")" | "a" | Lambda ;
+//
+// Nonterminals are the names defined on the left side of a rule, they can
+// end with primes as in E', and the start symbol is the first one of them.
+// Terminals are quoted with '"' or '\''. Lambda (or λ) and empty alternatives
+// stand for the empty string, '::=' can be used instead of '->' and the ';'
+// ending a rule is optional.
+// EBNF groups '( )' and the '?', '*' and '+' postfix operators are
+// desugared into plain productions with fresh nonterminals:
+//
+//     A -> x?    becomes    A -> A_1 ;  A_1 -> x | Lambda
+//     A -> x*    becomes    A -> A_1 ;  A_1 -> x A_1 | Lambda
+//     A -> x+    becomes    A -> x A_1 ;  A_1 -> x A_1 | Lambda
+
+use std::collections::BTreeSet;
+
+use automata_text::{ParseError, ParseResult};
+use cfg::{CFG, TNT, Derivation};
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Quoted(String),
+    Lambda,
+    Arrow,
+    Bar,
+    Semi,
+    LParen,
+    RParen,
+    Opt,
+    Star,
+    Plus,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    tok: Tok,
+    line: usize,
+    col: usize,
+}
+
+fn error<T, S: Into<String>>(line: usize, col: usize, msg: S) -> ParseResult<T> {
+    Err(ParseError {
+            line,
+            col,
+            msg: msg.into(),
+        })
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn tokenize(src: &str) -> ParseResult<Vec<Spanned>> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let (mut line, mut col) = (1, 1);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let (start_line, start_col) = (line, col);
+
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            col = 1;
+            continue;
+        }
+
+        if c.is_whitespace() {
+            i += 1;
+            col += 1;
+            continue;
+        }
+
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+                col += 1;
+            }
+            continue;
+        }
+
+        let (tok, len) = if c == '"' || c == '\'' {
+            let mut s = String::new();
+            let mut j = i + 1;
+            loop {
+                match chars.get(j) {
+                    None | Some(&'\n') => {
+                        return error(start_line, start_col, "unterminated quoted terminal")
+                    }
+                    Some(&'\\') => {
+                        match chars.get(j + 1) {
+                            Some(&e) if e == c || e == '\\' => s.push(e),
+                            _ => return error(line, col + j - i, "invalid escape sequence"),
+                        }
+                        j += 2;
+                    }
+                    Some(&e) if e == c => break,
+                    Some(&e) => {
+                        s.push(e);
+                        j += 1;
+                    }
+                }
+            }
+
+            if s.is_empty() {
+                return error(start_line, start_col, "empty terminal, use Lambda instead");
+            }
+
+            (Tok::Quoted(s), j + 1 - i)
+        } else if is_ident_start(c) {
+            let mut j = i;
+            while j < chars.len() && is_ident_char(chars[j]) {
+                j += 1;
+            }
+            // Trailing primes as in E' are part of the name
+            // as long as they do not open a quoted terminal
+            let mut primes = j;
+            while primes < chars.len() && chars[primes] == '\'' {
+                primes += 1;
+            }
+            if primes == chars.len() || !is_ident_char(chars[primes]) {
+                j = primes;
+            }
+            let ident: String = chars[i..j].iter().collect();
+            let tok = if ident == "Lambda" || ident == "λ" {
+                Tok::Lambda
+            } else {
+                Tok::Ident(ident)
+            };
+
+            (tok, j - i)
+        } else {
+            let next = chars.get(i + 1).cloned();
+            let next2 = chars.get(i + 2).cloned();
+            match (c, next, next2) {
+                ('-', Some('>'), _) => (Tok::Arrow, 2),
+                (':', Some(':'), Some('=')) => (Tok::Arrow, 3),
+                ('|', _, _) => (Tok::Bar, 1),
+                (';', _, _) => (Tok::Semi, 1),
+                ('(', _, _) => (Tok::LParen, 1),
+                (')', _, _) => (Tok::RParen, 1),
+                ('?', _, _) => (Tok::Opt, 1),
+                ('*', _, _) => (Tok::Star, 1),
+                ('+', _, _) => (Tok::Plus, 1),
+                _ => {
+                    return error(line,
+                                 col,
+                                 format!("unexpected {:?}, terminals must be quoted", c))
+                }
+            }
+        };
+
+        tokens.push(Spanned {
+                        tok,
+                        line: start_line,
+                        col: start_col,
+                    });
+        i += len;
+        col += len;
+    }
+
+    tokens.push(Spanned {
+                    tok: Tok::Eof,
+                    line,
+                    col,
+                });
+
+    Ok(tokens)
+}
+
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Ident(String, usize, usize),
+    Quoted(String),
+    Lambda,
+    Group(Vec<Vec<Expr>>),
+    Opt(Box<Expr>),
+    Star(Box<Expr>),
+    Plus(Box<Expr>),
+}
+
+impl Expr {
+    fn terminals(&self, acc: &mut BTreeSet<String>) {
+        match *self {
+            Expr::Quoted(ref s) => {
+                acc.insert(s.clone());
+            }
+            Expr::Group(ref alternatives) => {
+                for e in alternatives.iter().flatten() {
+                    e.terminals(acc);
+                }
+            }
+            Expr::Opt(ref e) | Expr::Star(ref e) | Expr::Plus(ref e) => e.terminals(acc),
+            Expr::Ident(..) | Expr::Lambda => {}
+        }
+    }
+}
+
+struct Rule {
+    lhs: String,
+    line: usize,
+    col: usize,
+    alternatives: Vec<Vec<Expr>>,
+}
+
+struct BnfParser {
+    tokens: Vec<Spanned>,
+    index: usize,
+}
+
+impl BnfParser {
+    fn peek(&self) -> &Spanned {
+        &self.tokens[self.index]
+    }
+
+    fn peek_tok(&self, offset: usize) -> &Tok {
+        let i = ::std::cmp::min(self.index + offset, self.tokens.len() - 1);
+        &self.tokens[i].tok
+    }
+
+    fn bump(&mut self) -> Spanned {
+        let t = self.tokens[self.index].clone();
+        if self.index < self.tokens.len() - 1 {
+            self.index += 1;
+        }
+        t
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> ParseResult<T> {
+        let t = self.peek();
+        error(t.line,
+              t.col,
+              format!("expected {} but found {}", expected, describe(&t.tok)))
+    }
+
+    fn rules(&mut self) -> ParseResult<Vec<Rule>> {
+        let mut rules = vec![];
+        loop {
+            match *self.peek_tok(0) {
+                Tok::Eof => return Ok(rules),
+                Tok::Semi => {
+                    self.bump();
+                }
+                Tok::Ident(_) => {
+                    let t = self.bump();
+                    let name = match t.tok {
+                        Tok::Ident(name) => name,
+                        _ => unreachable!(),
+                    };
+                    if *self.peek_tok(0) != Tok::Arrow {
+                        return self.unexpected("'->'");
+                    }
+                    self.bump();
+                    let alternatives = self.alternatives()?;
+                    match *self.peek_tok(0) {
+                        Tok::Semi | Tok::Eof | Tok::Ident(_) => {}
+                        _ => return self.unexpected("'|', ';' or a new rule"),
+                    }
+                    rules.push(Rule {
+                                   lhs: name,
+                                   line: t.line,
+                                   col: t.col,
+                                   alternatives,
+                               });
+                }
+                _ => return self.unexpected("a rule"),
+            }
+        }
+    }
+
+    // A rule ends with ';', the end of the input or
+    // the start of the next rule (an identifier followed by '->')
+    fn at_sequence_end(&self) -> bool {
+        match *self.peek_tok(0) {
+            Tok::Semi | Tok::Eof | Tok::Bar | Tok::RParen => true,
+            Tok::Ident(_) => *self.peek_tok(1) == Tok::Arrow,
+            _ => false,
+        }
+    }
+
+    fn alternatives(&mut self) -> ParseResult<Vec<Vec<Expr>>> {
+        let mut alternatives = vec![self.sequence()?];
+        while *self.peek_tok(0) == Tok::Bar {
+            self.bump();
+            alternatives.push(self.sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut sequence = vec![];
+        while !self.at_sequence_end() {
+            let mut e = self.primary()?;
+            loop {
+                e = match *self.peek_tok(0) {
+                    Tok::Opt => Expr::Opt(Box::new(e)),
+                    Tok::Star => Expr::Star(Box::new(e)),
+                    Tok::Plus => Expr::Plus(Box::new(e)),
+                    _ => break,
+                };
+                self.bump();
+            }
+            sequence.push(e);
+        }
+
+        Ok(sequence)
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        match self.peek_tok(0).clone() {
+            Tok::Ident(name) => {
+                let t = self.bump();
+                Ok(Expr::Ident(name, t.line, t.col))
+            }
+            Tok::Quoted(s) => {
+                self.bump();
+                Ok(Expr::Quoted(s))
+            }
+            Tok::Lambda => {
+                self.bump();
+                Ok(Expr::Lambda)
+            }
+            Tok::LParen => {
+                self.bump();
+                let alternatives = self.alternatives()?;
+                if *self.peek_tok(0) != Tok::RParen {
+                    return self.unexpected("')'");
+                }
+                self.bump();
+                Ok(Expr::Group(alternatives))
+            }
+            _ => self.unexpected("a symbol"),
+        }
+    }
+}
+
+fn describe(tok: &Tok) -> String {
+    match *tok {
+        Tok::Ident(ref name) => format!("{:?}", name),
+        Tok::Quoted(ref s) => format!("terminal {:?}", s),
+        Tok::Lambda => "Lambda".to_string(),
+        Tok::Arrow => "'->'".to_string(),
+        Tok::Bar => "'|'".to_string(),
+        Tok::Semi => "';'".to_string(),
+        Tok::LParen => "'('".to_string(),
+        Tok::RParen => "')'".to_string(),
+        Tok::Opt => "'?'".to_string(),
+        Tok::Star => "'*'".to_string(),
+        Tok::Plus => "'+'".to_string(),
+        Tok::Eof => "the end of the input".to_string(),
+    }
+}
+
+
+struct Desugar {
+    vn: BTreeSet<String>,
+    vt: BTreeSet<String>,
+    p: Vec<(String, Derivation)>,
+    fresh_index: usize,
+}
+
+impl Desugar {
+    fn fresh(&mut self, lhs: &str) -> String {
+        loop {
+            self.fresh_index += 1;
+            let name = format!("{}_{}", lhs, self.fresh_index);
+            if !self.vn.contains(&name) && !self.vt.contains(&name) {
+                self.vn.insert(name.clone());
+                return name;
+            }
+        }
+    }
+
+    fn push(&mut self, lhs: String, der: Derivation) {
+        let der = if der.is_empty() {
+            vec![TNT::Lambda]
+        } else {
+            der
+        };
+        self.p.push((lhs, der));
+    }
+
+    fn sequence(&mut self, lhs: &str, sequence: &[Expr]) -> ParseResult<Derivation> {
+        let mut der = vec![];
+        for e in sequence {
+            der.extend(self.expr(lhs, e)?);
+        }
+
+        Ok(der)
+    }
+
+    fn expr(&mut self, lhs: &str, e: &Expr) -> ParseResult<Derivation> {
+        match *e {
+            Expr::Ident(ref name, line, col) => {
+                if !self.vn.contains(name) {
+                    return error(line,
+                                 col,
+                                 format!("undefined nonterminal {:?}, quote it if it is a \
+                                          terminal",
+                                         name));
+                }
+                Ok(vec![TNT::NT(name.clone())])
+            }
+            Expr::Quoted(ref s) => {
+                self.vt.insert(s.clone());
+                Ok(vec![TNT::T(s.clone())])
+            }
+            Expr::Lambda => Ok(vec![]),
+            Expr::Group(ref alternatives) => {
+                if alternatives.len() == 1 {
+                    return self.sequence(lhs, &alternatives[0]);
+                }
+
+                let nt = self.fresh(lhs);
+                for sequence in alternatives {
+                    let der = self.sequence(lhs, sequence)?;
+                    self.push(nt.clone(), der);
+                }
+                Ok(vec![TNT::NT(nt)])
+            }
+            Expr::Opt(ref e) => {
+                let nt = self.fresh(lhs);
+                let der = self.expr(lhs, e)?;
+                self.push(nt.clone(), der);
+                self.push(nt.clone(), vec![]);
+                Ok(vec![TNT::NT(nt)])
+            }
+            Expr::Star(ref e) => {
+                let nt = self.fresh(lhs);
+                let mut der = self.expr(lhs, e)?;
+                der.push(TNT::NT(nt.clone()));
+                self.push(nt.clone(), der);
+                self.push(nt.clone(), vec![]);
+                Ok(vec![TNT::NT(nt)])
+            }
+            Expr::Plus(ref e) => {
+                let nt = self.fresh(lhs);
+                let mut der = self.expr(lhs, e)?;
+                let mut star_der = der.clone();
+                star_der.push(TNT::NT(nt.clone()));
+                self.push(nt.clone(), star_der);
+                self.push(nt.clone(), vec![]);
+                der.push(TNT::NT(nt));
+                Ok(der)
+            }
+        }
+    }
+}
+
+
+/// Parses a BNF / EBNF grammar, EBNF operators are desugared
+/// into plain productions.
+pub fn parse_grammar(src: &str) -> ParseResult<CFG> {
+    let tokens = tokenize(src)?;
+    let mut parser = BnfParser {
+        tokens,
+        index: 0,
+    };
+    let rules = parser.rules()?;
+
+    if rules.is_empty() {
+        let t = parser.peek();
+        return error(t.line, t.col, "the grammar has no rules");
+    }
+
+    // Every terminal is known before the fresh nonterminals are named
+    let mut vt = BTreeSet::new();
+    for e in rules.iter().flat_map(|r| r.alternatives.iter().flatten()) {
+        e.terminals(&mut vt);
+    }
+
+    let mut desugar = Desugar {
+        vn: rules.iter().map(|r| r.lhs.clone()).collect(),
+        vt,
+        p: vec![],
+        fresh_index: 0,
+    };
+
+    for rule in &rules {
+        for sequence in &rule.alternatives {
+            let der = desugar.sequence(&rule.lhs, sequence)?;
+            desugar.push(rule.lhs.clone(), der);
+        }
+    }
+
+    for rule in &rules {
+        if desugar.vt.contains(&rule.lhs) {
+            return error(rule.line,
+                         rule.col,
+                         format!("{:?} is both a terminal and a nonterminal", rule.lhs));
+        }
+    }
+
+    let s = rules[0].lhs.clone();
+    CFG::try_from_derivations(desugar.vn, desugar.vt, desugar.p, s)
+        .or_else(|msg| error(rules[0].line, rules[0].col, msg))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::{t, nt};
+
+    fn set(symbols: &[&str]) -> BTreeSet<String> {
+        symbols.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_grammar_test() {
+        let src = "# expressions\n\
+                   Expr -> Expr \"+\" Term | Term ;\n\
+                   Term ::= '(' Expr ')'\n\
+                        | \"num\"\n\
+                        | Lambda";
+
+        let g = parse_grammar(src).unwrap();
+
+        assert_eq!(g.s, "Expr");
+        assert_eq!(g.vn, set(&["Expr", "Term"]));
+        assert_eq!(g.vt, set(&["+", "(", ")", "num"]));
+        assert_eq!(g.p["Expr"], vec![vec![nt("Expr"), t("+"), nt("Term")], vec![nt("Term")]]);
+        assert_eq!(g.p["Term"],
+                   vec![vec![t("("), nt("Expr"), t(")")], vec![t("num")], vec![TNT::Lambda]]);
+    }
+
+    #[test]
+    fn parse_grammar_ebnf_test() {
+        let src = "List -> \"[\" (Item (\",\" Item)*)? \"]\"\n\
+                   Item -> \"a\"+ | λ";
+
+        let g = parse_grammar(src).unwrap();
+
+        assert_eq!(g.vn, set(&["List", "List_1", "List_2", "Item", "Item_3"]));
+        assert_eq!(g.p["List"], vec![vec![t("["), nt("List_1"), t("]")]]);
+        assert_eq!(g.p["List_1"], vec![vec![nt("Item"), nt("List_2")], vec![TNT::Lambda]]);
+        assert_eq!(g.p["List_2"],
+                   vec![vec![t(","), nt("Item"), nt("List_2")], vec![TNT::Lambda]]);
+        assert_eq!(g.p["Item"], vec![vec![t("a"), nt("Item_3")], vec![TNT::Lambda]]);
+        assert_eq!(g.p["Item_3"], vec![vec![t("a"), nt("Item_3")], vec![TNT::Lambda]]);
+    }
+
+    #[test]
+    fn parse_grammar_fresh_names_test() {
+        let g = parse_grammar("A -> \"a\"? A_1 ; A_1 -> \"b\"").unwrap();
+
+        assert_eq!(g.vn, set(&["A", "A_1", "A_2"]));
+        assert_eq!(g.p["A"], vec![vec![nt("A_2"), nt("A_1")]]);
+
+        // Fresh names skip the quoted terminals too
+        let g = parse_grammar("A -> \"a\"? \"A_1\"").unwrap();
+        assert_eq!(g.vn, set(&["A", "A_2"]));
+        assert_eq!(g.p["A"], vec![vec![nt("A_2"), t("A_1")]]);
+    }
+
+    #[test]
+    fn parse_grammar_errors_test() {
+        let cases = vec![("S -> a", (1, 6), "undefined nonterminal \"a\", quote it if it is a terminal"),
+                         ("S -> \"a\n", (1, 6), "unterminated quoted terminal"),
+                         ("S -> \"a\" )", (1, 10), "expected '|', ';' or a new rule but found ')'"),
+                         ("S \"a\"", (1, 3), "expected '->' but found terminal \"a\""),
+                         ("S -> (\"a\"", (1, 10), "expected ')' but found the end of the input"),
+                         ("S -> \"a\"\n  -> \"c\"", (2, 3), "expected a symbol but found '->'"),
+                         ("S -> \"a\" =", (1, 10), "unexpected '=', terminals must be quoted"),
+                         ("S -> \"S\"", (1, 1), "\"S\" is both a terminal and a nonterminal"),
+                         ("# nothing", (1, 10), "the grammar has no rules")];
+
+        for (src, (line, col), msg) in cases {
+            let err = parse_grammar(src).unwrap_err();
+            assert_eq!(err,
+                       ParseError {
+                           line,
+                           col,
+                           msg: msg.to_string(),
+                       },
+                       "In {:?}",
+                       src);
+        }
+    }
+
+    #[test]
+    fn primed_names_test() {
+        let g = parse_grammar("E -> \"a\" E' ; E' -> \"+\" E'' | 'b' ; E''->'c'").unwrap();
+
+        assert_eq!(g.vn, set(&["E", "E'", "E''"]));
+        assert_eq!(g.vt, set(&["a", "+", "b", "c"]));
+        assert_eq!(g.p["E'"], vec![vec![t("+"), nt("E''")], vec![t("b")]]);
+    }
+
+    #[test]
+    fn multi_char_symbols_test() {
+        let g = parse_grammar("Stmt -> \"if\" Cond \"then\" Stmt | \"skip\" ; Cond -> \"true\"")
+            .unwrap();
+
+        assert_eq!(g.vt, set(&["if", "then", "skip", "true"]));
+        assert_eq!(g.p["Stmt"][0], vec![t("if"), nt("Cond"), t("then"), nt("Stmt")]);
+        assert!(g.to_string().contains("Stmt -> if Cond then Stmt"));
+    }
+
+    #[test]
+    fn grammar_md_test() {
+        let md = include_str!("../grammar.md");
+        let start = md.find("```\n").unwrap() + 4;
+        let end = start + md[start..].find("```").unwrap();
+
+        let g = parse_grammar(&md[start..end]).unwrap();
+
+        assert_eq!(g.s, "Re");
+        assert_eq!(g.vn, set(&["Re", "Ops", "ReL"]));
+        assert_eq!(g.vt, set(&["Lit", "(", ")", "*", "+", "|"]));
+        assert_eq!(g.p.values().map(|dervec| dervec.len()).sum::<usize>(), 9);
+        assert_eq!(g.p["Re"][1], vec![t("("), nt("Re"), t(")"), nt("Ops")]);
+        assert_eq!(g.p["Ops"][4], vec![TNT::Lambda]);
+    }
+}
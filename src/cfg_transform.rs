@@ -0,0 +1,365 @@
+// Transformations that turn a grammar into an equivalent one, usually
+// to make it suitable for LL(1) parsing. Every transformation returns the
+// new grammar along with the steps it took so hand derived results can be
+// checked one step at a time.
+
+use std::fmt;
+
+use cfg::{CFG, TNT, Derivation, DerivationVec, derivation_to_text};
+
+
+/// A single change made by a transformation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformStep {
+    /// `nt -> replaced` was substituted by `nt -> δ γ` for every
+    /// derivation δ of the nonterminal leading `replaced`.
+    Substituted {
+        nt: String,
+        replaced: Derivation,
+        by: DerivationVec,
+    },
+    /// `nt -> nt α | β` became `nt -> β fresh` and `fresh -> α fresh | λ`.
+    DirectRecursionRemoved { nt: String, fresh: String },
+    /// The alternatives of `nt` starting with `prefix` became
+    /// `nt -> prefix fresh` with their suffixes moved to `fresh`.
+    Factored {
+        nt: String,
+        prefix: Derivation,
+        fresh: String,
+    },
+}
+
+impl fmt::Display for TransformStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransformStep::Substituted { ref nt, ref replaced, ref by } => {
+                let by: Vec<String> = by.iter().map(derivation_to_text).collect();
+                write!(f,
+                       "Substituted {} -> {} by {} -> {}",
+                       nt,
+                       derivation_to_text(replaced),
+                       nt,
+                       by.join(" | "))
+            }
+            TransformStep::DirectRecursionRemoved { ref nt, ref fresh } => {
+                write!(f, "Removed direct left recursion of {} with {}", nt, fresh)
+            }
+            TransformStep::Factored { ref nt, ref prefix, ref fresh } => {
+                write!(f,
+                       "Factored {} out of {} into {}",
+                       derivation_to_text(prefix),
+                       nt,
+                       fresh)
+            }
+        }
+    }
+}
+
+
+impl CFG {
+    /// Nonterminal named after `base` that is not a symbol of the grammar yet,
+    /// primes are appended until the name is free.
+    pub fn fresh_nonterminal(&self, base: &str) -> String {
+        let mut name = format!("{}'", base);
+        while self.vn.contains(&name) || self.vt.contains(&name) {
+            name.push('\'');
+        }
+
+        name
+    }
+
+    /// Removes direct and indirect left recursion ordering the nonterminals
+    /// with the start symbol first and then the rest as in VN.
+    pub fn eliminate_left_recursion(&self) -> (CFG, Vec<TransformStep>) {
+        let mut order = vec![self.s.clone()];
+        order.extend(self.vn.iter().filter(|&nt| *nt != self.s).cloned());
+
+        self.eliminate_left_recursion_ordered(&order)
+    }
+
+    /// Classic algorithm over the given ordering A1..An, every Ai -> Aj γ
+    /// with j < i is expanded and then the direct recursion of Ai is removed.
+    /// The result is only guaranteed when the grammar has no cycles
+    /// (A =>+ A) nor λ productions other than the ones it introduces.
+    pub fn eliminate_left_recursion_ordered(&self, order: &[String]) -> (CFG, Vec<TransformStep>) {
+        let mut g = self.clone();
+        let mut trace = vec![];
+
+        for (i, ai) in order.iter().enumerate() {
+            for aj in &order[..i] {
+                let dervec = g.p.get(ai).cloned().unwrap_or_default();
+                let mut new_dervec = vec![];
+                for der in dervec {
+                    if der.first() != Some(&TNT::NT(aj.clone())) {
+                        new_dervec.push(der);
+                        continue;
+                    }
+
+                    let by: DerivationVec = g.p[aj]
+                        .iter()
+                        .map(|delta| concat(delta, &der[1..]))
+                        .collect();
+
+                    trace.push(TransformStep::Substituted {
+                                   nt: ai.clone(),
+                                   replaced: der,
+                                   by: by.clone(),
+                               });
+                    new_dervec.extend(by);
+                }
+                g.p.insert(ai.clone(), new_dervec);
+            }
+
+            if let Some(step) = remove_direct_recursion(&mut g, ai) {
+                trace.push(step);
+            }
+        }
+
+        (g, trace)
+    }
+
+    /// Factors the longest common prefix out of the alternatives of every
+    /// nonterminal until no two alternatives start with the same symbol.
+    pub fn left_factor(&self) -> (CFG, Vec<TransformStep>) {
+        let mut g = self.clone();
+        let mut trace = vec![];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let nts: Vec<String> = g.p.keys().cloned().collect();
+            for nt in nts {
+                while let Some(step) = factor_once(&mut g, &nt) {
+                    trace.push(step);
+                    changed = true;
+                }
+            }
+        }
+
+        (g, trace)
+    }
+}
+
+
+// Appends the rest to the derivation, Lambda is dropped
+// unless the result would be empty
+fn concat(der: &[TNT], rest: &[TNT]) -> Derivation {
+    let joined: Derivation = der.iter()
+        .chain(rest.iter())
+        .filter(|&e| *e != TNT::Lambda)
+        .cloned()
+        .collect();
+
+    if joined.is_empty() {
+        vec![TNT::Lambda]
+    } else {
+        joined
+    }
+}
+
+fn remove_direct_recursion(g: &mut CFG, nt: &str) -> Option<TransformStep> {
+    let dervec = g.p.get(nt).cloned().unwrap_or_default();
+    let nt_symbol = TNT::NT(nt.to_string());
+
+    let (recursive, rest): (DerivationVec, DerivationVec) =
+        dervec.into_iter().partition(|der| der.first() == Some(&nt_symbol));
+
+    if recursive.is_empty() {
+        return None;
+    }
+
+    let fresh = g.fresh_nonterminal(nt);
+    let fresh_tail = [TNT::NT(fresh.clone())];
+
+    let new_dervec = rest.iter().map(|beta| concat(beta, &fresh_tail)).collect();
+
+    // A -> A is a cycle that adds nothing to the language
+    let mut fresh_dervec: DerivationVec = recursive.iter()
+        .filter(|der| der.len() > 1)
+        .map(|der| concat(&der[1..], &fresh_tail))
+        .collect();
+    fresh_dervec.push(vec![TNT::Lambda]);
+
+    g.vn.insert(fresh.clone());
+    g.p.insert(nt.to_string(), new_dervec);
+    g.p.insert(fresh.clone(), fresh_dervec);
+
+    Some(TransformStep::DirectRecursionRemoved {
+             nt: nt.to_string(),
+             fresh,
+         })
+}
+
+fn factor_once(g: &mut CFG, nt: &str) -> Option<TransformStep> {
+    let dervec = g.p[nt].clone();
+
+    // First alternative sharing its leading symbol with a later one,
+    // empty and Lambda alternatives have none
+    fn leading(der: &Derivation) -> Option<&TNT> {
+        der.first().filter(|&e| *e != TNT::Lambda)
+    }
+    let (first, group): (usize, Vec<usize>) = dervec.iter()
+        .enumerate()
+        .filter_map(|(i, der)| leading(der).map(|e| (i, e)))
+        .map(|(i, e)| {
+                 let group =
+                     (i..dervec.len()).filter(|&j| leading(&dervec[j]) == Some(e)).collect();
+                 (i, group)
+             })
+        .find(|(_, group): &(usize, Vec<usize>)| group.len() > 1)?;
+
+    let mut prefix_len = 1;
+    while group.iter().all(|&j| dervec[j].len() > prefix_len &&
+                                dervec[j][prefix_len] == dervec[first][prefix_len]) {
+        prefix_len += 1;
+    }
+    let prefix: Derivation = dervec[first][..prefix_len].to_vec();
+
+    let fresh = g.fresh_nonterminal(nt);
+    let fresh_dervec: DerivationVec = group.iter()
+        .map(|&j| concat(&dervec[j][prefix_len..], &[]))
+        .collect();
+
+    let mut new_dervec = vec![];
+    for (i, der) in dervec.into_iter().enumerate() {
+        if i == first {
+            new_dervec.push(concat(&prefix, &[TNT::NT(fresh.clone())]));
+        } else if !group.contains(&i) {
+            new_dervec.push(der);
+        }
+    }
+
+    g.vn.insert(fresh.clone());
+    g.p.insert(nt.to_string(), new_dervec);
+    g.p.insert(fresh.clone(), fresh_dervec);
+
+    Some(TransformStep::Factored {
+             nt: nt.to_string(),
+             prefix,
+             fresh,
+         })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg::t;
+    use cfg_text::parse_grammar;
+
+    fn productions(g: &CFG, nt: &str) -> Vec<String> {
+        g.p[nt].iter().map(derivation_to_text).collect()
+    }
+
+    #[test]
+    fn direct_left_recursion_test() {
+        let g = parse_grammar("E -> E \"+\" T | T ; T -> T \"*\" \"id\" | \"id\"").unwrap();
+        let (g2, trace) = g.eliminate_left_recursion();
+
+        assert_eq!(productions(&g2, "E"), vec!["T E'"]);
+        assert_eq!(productions(&g2, "E'"), vec!["+ T E'", "λ"]);
+        assert_eq!(productions(&g2, "T"), vec!["id T'"]);
+        assert_eq!(productions(&g2, "T'"), vec!["* id T'", "λ"]);
+        assert_eq!(g2.vn, stateset!("E", "E'", "T", "T'"));
+        assert_eq!(trace.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
+                   vec!["Removed direct left recursion of E with E'",
+                        "Removed direct left recursion of T with T'"]);
+
+        assert!(g.ll1_table().is_err());
+        assert!(g2.ll1_table().is_ok());
+    }
+
+    #[test]
+    fn indirect_left_recursion_test() {
+        // Dragon book 4.20
+        let g = parse_grammar("S -> A \"a\" | \"b\" ; A -> A \"c\" | S \"d\" | Lambda").unwrap();
+        let (g2, trace) = g.eliminate_left_recursion();
+
+        assert_eq!(productions(&g2, "S"), vec!["A a", "b"]);
+        assert_eq!(productions(&g2, "A"), vec!["b d A'", "A'"]);
+        assert_eq!(productions(&g2, "A'"), vec!["c A'", "a d A'", "λ"]);
+        assert_eq!(trace,
+                   vec![TransformStep::Substituted {
+                            nt: "A".to_string(),
+                            replaced: vec![TNT::NT("S".to_string()), TNT::T("d".to_string())],
+                            by: vec![vec![TNT::NT("A".to_string()),
+                                          TNT::T("a".to_string()),
+                                          TNT::T("d".to_string())],
+                                     vec![TNT::T("b".to_string()), TNT::T("d".to_string())]],
+                        },
+                        TransformStep::DirectRecursionRemoved {
+                            nt: "A".to_string(),
+                            fresh: "A'".to_string(),
+                        }]);
+    }
+
+    #[test]
+    fn left_recursion_ordered_test() {
+        let g = parse_grammar("S -> A \"a\" | \"b\" ; A -> S \"c\"").unwrap();
+        let (g2, trace) = g.eliminate_left_recursion_ordered(&["A".to_string(), "S".to_string()]);
+
+        assert_eq!(productions(&g2, "A"), vec!["S c"]);
+        assert_eq!(productions(&g2, "S"), vec!["b S'"]);
+        assert_eq!(productions(&g2, "S'"), vec!["c a S'", "λ"]);
+        assert_eq!(trace.len(), 2);
+    }
+
+    #[test]
+    fn fresh_nonterminal_test() {
+        let g = parse_grammar("E -> E' \"+\" | \"a\" ; E' -> \"b\"").unwrap();
+        assert_eq!(g.fresh_nonterminal("E"), "E''");
+        assert_eq!(g.fresh_nonterminal("F"), "F'");
+    }
+
+    #[test]
+    fn left_factor_test() {
+        let g = parse_grammar("S -> \"if\" E \"then\" S | \"if\" E \"then\" S \"else\" S | \"a\" ;\
+                               E -> \"b\"")
+            .unwrap();
+        let (g2, trace) = g.left_factor();
+
+        assert_eq!(productions(&g2, "S"), vec!["if E then S S'", "a"]);
+        assert_eq!(productions(&g2, "S'"), vec!["λ", "else S"]);
+        assert_eq!(trace.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
+                   vec!["Factored if E then S out of S into S'"]);
+    }
+
+    #[test]
+    fn left_factor_empty_derivation_test() {
+        use std::collections::BTreeMap;
+
+        // An empty derivation passes CFG::check
+        let mut p = BTreeMap::new();
+        p.insert("A".to_string(), vec![vec![], vec![t("a"), t("b")], vec![t("a")]]);
+        let g = CFG {
+            vn: stateset!("A"),
+            vt: stateset!("a", "b"),
+            p,
+            s: "A".to_string(),
+        };
+        let (g2, trace) = g.left_factor();
+
+        assert_eq!(productions(&g2, "A"), vec!["", "a A'"]);
+        assert_eq!(productions(&g2, "A'"), vec!["b", "λ"]);
+        assert_eq!(trace.len(), 1);
+    }
+
+    #[test]
+    fn left_factor_nested_test() {
+        let g = parse_grammar("A -> \"a\" \"b\" | \"a\" \"c\" | \"a\" | \"d\"").unwrap();
+        let (g2, trace) = g.left_factor();
+
+        assert_eq!(productions(&g2, "A"), vec!["a A'", "d"]);
+        assert_eq!(productions(&g2, "A'"), vec!["b", "c", "λ"]);
+        assert_eq!(trace.len(), 1);
+        assert!(g2.ll1_table().is_ok());
+
+        let g = parse_grammar("A -> \"a\" \"b\" \"c\" | \"a\" \"b\" \"d\" | \"a\" \"e\"").unwrap();
+        let (g2, trace) = g.left_factor();
+
+        assert_eq!(productions(&g2, "A"), vec!["a A'"]);
+        assert_eq!(productions(&g2, "A'"), vec!["b A''", "e"]);
+        assert_eq!(productions(&g2, "A''"), vec!["c", "d"]);
+        assert_eq!(trace.len(), 2);
+    }
+}
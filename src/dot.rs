@@ -0,0 +1,74 @@
+// Helpers to emit Graphviz DOT documents.
+//
+// Terminals and nonterminals of a parse tree are drawn with different shapes
+// so the expansions made by a parser are easy to follow visually.
+
+
+pub fn escape(label: &str) -> String {
+    let mut escaped = String::new();
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+pub fn terminal_node(id: usize, label: &str) -> String {
+    format!("    n{} [label=\"{}\", shape=box, style=filled, fillcolor=lightgrey];\n",
+            id,
+            escape(label))
+}
+
+pub fn non_terminal_node(id: usize, label: &str) -> String {
+    format!("    n{} [label=\"{}\", shape=ellipse];\n", id, escape(label))
+}
+
+pub fn state_node(id: usize, label: &str) -> String {
+    format!("    n{} [label=\"{}\", shape=box];\n", id, escape(label))
+}
+
+pub fn edge(from: usize, to: usize) -> String {
+    format!("    n{} -> n{};\n", from, to)
+}
+
+pub fn labeled_edge(from: usize, to: usize, label: &str) -> String {
+    format!("    n{} -> n{} [label=\"{}\"];\n", from, to, escape(label))
+}
+
+pub fn digraph(name: &str, body: &str) -> String {
+    format!("digraph {} {{\n    ordering=out;\n{}}}\n", name, body)
+}
+
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn escape_test() {
+        use super::escape;
+
+        assert_eq!(escape("a"), "a");
+        assert_eq!(escape("\"a\""), "\\\"a\\\"");
+        assert_eq!(escape("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn digraph_test() {
+        use super::{digraph, non_terminal_node, terminal_node, edge};
+
+        let mut body = String::new();
+        body.push_str(&non_terminal_node(0, "S"));
+        body.push_str(&terminal_node(1, "a"));
+        body.push_str(&edge(0, 1));
+
+        let expected = "digraph tree {\n    ordering=out;\n    n0 [label=\"S\", shape=ellipse];\n    n1 \
+                        [label=\"a\", shape=box, style=filled, fillcolor=lightgrey];\n    n0 -> \
+                        n1;\n}\n";
+
+        assert_eq!(digraph("tree", &body), expected);
+    }
+}
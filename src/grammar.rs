@@ -1,29 +1,56 @@
 use std::collections::BTreeSet;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
-pub type Terminal = char;
-pub type TerminalSet = BTreeSet<Terminal>;
-pub type NonTerminal = char;
-pub type NonTerminalSet = BTreeSet<NonTerminal>;
-pub type Chain = String;
+use symbol::Symbol;
 
+pub type Terminal<S = char> = S;
+pub type TerminalSet<S = char> = BTreeSet<Terminal<S>>;
+pub type NonTerminal<S = char> = S;
+pub type NonTerminalSet<S = char> = BTreeSet<NonTerminal<S>>;
+pub type Chain<S = char> = Vec<S>;
 
-pub type RegularProductions = BTreeSet<(NonTerminal, Chain)>;
+
+pub type RegularProductions<S = char> = BTreeSet<(NonTerminal<S>, Chain<S>)>;
 //pub type RegularProductionsMap = BTreeMap<NonTerminal, BTreeSet<Chain>>;
 
 #[derive(Clone, Debug)]
-pub struct GR {
-    vt: TerminalSet,
-    vn: NonTerminalSet,
-    productions: RegularProductions,
-    q0: Terminal,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GRSchema<S>"))]
+pub struct GR<S: Symbol = char> {
+    pub vt: TerminalSet<S>,
+    pub vn: NonTerminalSet<S>,
+    pub productions: RegularProductions<S>,
+    pub q0: NonTerminal<S>,
+}
+
+/// JSON schema of GR, validated with `GR::check` when deserializing.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct GRSchema<S: Symbol> {
+    vt: TerminalSet<S>,
+    vn: NonTerminalSet<S>,
+    productions: RegularProductions<S>,
+    q0: NonTerminal<S>,
+}
+
+#[cfg(feature = "serde")]
+impl<S: Symbol> TryFrom<GRSchema<S>> for GR<S> {
+    type Error = String;
+
+    fn try_from(schema: GRSchema<S>) -> Result<GR<S>, String> {
+        GR::check(&schema.vt, &schema.vn, &schema.productions, &schema.q0)?;
+
+        Ok(GR::new(schema.vt, schema.vn, schema.productions, schema.q0))
+    }
 }
 
-impl GR {
-    pub fn new(vt: TerminalSet,
-               vn: NonTerminalSet,
-               productions: RegularProductions,
-               q0: Terminal)
-               -> GR {
+impl<S: Symbol> GR<S> {
+    pub fn new(vt: TerminalSet<S>,
+               vn: NonTerminalSet<S>,
+               productions: RegularProductions<S>,
+               q0: NonTerminal<S>)
+               -> GR<S> {
         GR {
             q0: q0,
             vt: vt,
@@ -31,12 +58,36 @@ impl GR {
             productions: productions,
         }
     }
+
+    pub fn check(vt: &TerminalSet<S>,
+                 vn: &NonTerminalSet<S>,
+                 productions: &RegularProductions<S>,
+                 q0: &NonTerminal<S>)
+                 -> Result<(), String> {
+        if !vn.is_disjoint(vt) {
+            return Err(format!("VN and VT must be disjoint.\nVN: {:?} \nVT: {:?}", vn, vt));
+        }
+
+        if !vn.contains(q0) {
+            return Err(format!("q0 {:?} does not belong to VN {:?}", q0, vn));
+        }
+
+        for (nt, chain) in productions {
+            if !vn.contains(nt) || chain.iter().any(|c| !vn.contains(c) && !vt.contains(c)) {
+                return Err(format!("Symbol in production {:?} -> {:?} does not belong to VN or VT",
+                                   nt,
+                                   chain));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 
 
-use automata::{M, StateSet};
-pub fn gr_to_afndl(gr: &GR) -> M {
+use automata::{M, StateSet, Delta};
+pub fn gr_to_afndl<S: Symbol>(gr: &GR<S>) -> M<S> {
 
     let alphabet = gr.vt.clone();
     let q0 = gr.q0.to_string();
@@ -51,17 +102,16 @@ pub fn gr_to_afndl(gr: &GR) -> M {
         k
     };
 
-    let mut delta = delta!();
-    for &(ref vn, ref chain) in &gr.productions {
-        let chain: Vec<char> = chain.chars().collect();
+    let mut delta: Delta<S> = BTreeSet::new();
+    for (vn, chain) in &gr.productions {
         match chain.len() {
             1 => {
-                let c = chain[0];
+                let c = chain[0].clone();
                 delta.insert((vn.to_string(), c, f.clone()));
             }
             2 => {
-                let c = chain[0];
-                let ns = chain[1];
+                let c = chain[0].clone();
+                let ns = &chain[1];
                 delta.insert((vn.to_string(), c, ns.to_string()));
             }
             _ => {
@@ -92,6 +142,21 @@ mod tests {
         println!("Resulted grammar {:?}", gr);
     }
 
+    #[test]
+    fn gr_check_test() {
+        use super::GR;
+
+        let productions = r_productions!(('S', "aS"), ('S', "a"));
+        assert!(GR::check(&charset!('a'), &charset!('S'), &productions, &'S').is_ok());
+        assert!(GR::check(&charset!('a'), &charset!('S'), &productions, &'A').is_err());
+        assert!(GR::check(&charset!('a', 'S'), &charset!('S'), &productions, &'S').is_err());
+
+        let productions = r_productions!(('S', "bS"));
+        assert!(GR::check(&charset!('a'), &charset!('S'), &productions, &'S').is_err());
+        let productions = r_productions!(('A', "a"));
+        assert!(GR::check(&charset!('a'), &charset!('S'), &productions, &'S').is_err());
+    }
+
     #[test]
     fn gr_to_afndl_test() {
         use super::{GR, NonTerminalSet, TerminalSet, NonTerminal, RegularProductions, gr_to_afndl};
@@ -113,4 +178,24 @@ mod tests {
         assert_eq!(m.alphabet, alphabet!('a'));
         assert_eq!(m.delta, to_delta_inner(delta_expected));
     }
+
+    #[test]
+    fn gr_to_afndl_tokens_test() {
+        use super::{GR, gr_to_afndl};
+        use automata_operators::afndl_to_afd;
+
+        let sym = |s: &str| s.to_string();
+        let productions = vec![(sym("S"), vec![sym("id"), sym("A")]),
+                               (sym("A"), vec![sym(","), sym("S")]),
+                               (sym("A"), vec![sym(";")])]
+            .into_iter()
+            .collect();
+
+        let gr = GR::new(stateset!("id", ",", ";"), stateset!("S", "A"), productions, sym("S"));
+        let mut m = afndl_to_afd(&gr_to_afndl(&gr));
+
+        assert!(m.check_symbols(&[sym("id"), sym(";")]).is_ok());
+        assert!(m.check_symbols(&[sym("id"), sym(","), sym("id"), sym(";")]).is_ok());
+        assert!(m.check_symbols(&[sym("id"), sym(",")]).is_err());
+    }
 }
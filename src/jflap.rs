@@ -0,0 +1,692 @@
+// Reader and writer for JFLAP .jff files.
+//
+// Finite automatas (<type>fa</type>) are mapped to M and grammars
+// (<type>grammar</type>) to either GR or CFG. JFLAP follows the convention
+// that variables are upper case letters and the start variable is the left
+// side of the first production, an empty <read/> or <right/> is lambda.
+
+use std::collections::BTreeMap;
+use std::result;
+
+use automata::{M, State, StateSet, Alphabet, Delta, to_delta};
+use grammar::{GR, NonTerminal, NonTerminalSet, TerminalSet, RegularProductions};
+use cfg::{CFG, derivation_to_string};
+
+pub type Result<T> = result::Result<T, String>;
+
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    attrs: BTreeMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> Vec<&'a Element> {
+        self.children.iter().filter(|c| c.name == name).collect()
+    }
+
+    fn child_text(&self, name: &str) -> Result<String> {
+        self.child(name)
+            .map(|c| c.text.trim().to_string())
+            .ok_or(format!("<{}> is missing <{}>", self.name, name))
+    }
+}
+
+
+struct XmlParser<'a> {
+    src: &'a str,
+    index: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn new(src: &'a str) -> XmlParser<'a> {
+        XmlParser { src, index: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.index..]
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T> {
+        Err(format!("XML error at byte {}: {}", self.index, msg))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.index += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_until(&mut self, end: &str) -> Result<()> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.index += i + end.len();
+                Ok(())
+            }
+            None => self.error(&format!("expected {:?}", end)),
+        }
+    }
+
+    // Skips the prolog, comments, doctypes and processing instructions
+    fn skip_misc(&mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return self.error("expected a name");
+        }
+        self.index += len;
+        Ok(rest[..len].to_string())
+    }
+
+    fn attr_value(&mut self) -> Result<String> {
+        let quote = match self.rest().chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => return self.error("expected a quoted attribute value"),
+        };
+        self.index += 1;
+        match self.rest().find(quote) {
+            Some(i) => {
+                let value = unescape(&self.rest()[..i]);
+                self.index += i + 1;
+                Ok(value)
+            }
+            None => self.error("unterminated attribute value"),
+        }
+    }
+
+    fn element(&mut self) -> Result<Element> {
+        if !self.rest().starts_with('<') {
+            return self.error("expected an element");
+        }
+        self.index += 1;
+
+        let mut element = Element {
+            name: self.name()?,
+            attrs: BTreeMap::new(),
+            children: vec![],
+            text: String::new(),
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.index += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.index += 1;
+                break;
+            }
+
+            let key = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return self.error("expected '=' after attribute name");
+            }
+            self.index += 1;
+            self.skip_whitespace();
+            let value = self.attr_value()?;
+            element.attrs.insert(key, value);
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return self.error(&format!("unclosed element <{}>", element.name));
+            }
+
+            if rest.starts_with("</") {
+                self.index += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return self.error(&format!("expected </{}> but found </{}>",
+                                               element.name,
+                                               name));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return self.error("expected '>'");
+                }
+                self.index += 1;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.index += "<![CDATA[".len();
+                let end = match self.rest().find("]]>") {
+                    Some(end) => end,
+                    None => return self.error("unterminated CDATA section"),
+                };
+                element.text.push_str(&self.rest()[..end]);
+                self.index += end + "]]>".len();
+            } else if rest.starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if rest.starts_with('<') {
+                let child = self.element()?;
+                element.children.push(child);
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&unescape(&rest[..end]));
+                self.index += end;
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<Element> {
+        self.skip_misc()?;
+        let root = self.element()?;
+        self.skip_misc()?;
+        if !self.rest().is_empty() {
+            return self.error("unexpected content after the root element");
+        }
+
+        Ok(root)
+    }
+}
+
+
+fn unescape(s: &str) -> String {
+    let mut res = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        res.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let entity = &rest[1..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => {
+                u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32)
+            }
+            _ if entity.starts_with('#') => {
+                entity[1..].parse::<u32>().ok().and_then(::std::char::from_u32)
+            }
+            _ => None,
+        };
+
+        match c {
+            Some(c) => {
+                res.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+
+    res
+}
+
+fn escape(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '&' => res.push_str("&amp;"),
+            '"' => res.push_str("&quot;"),
+            _ => res.push(c),
+        }
+    }
+
+    res
+}
+
+fn parse_structure(src: &str, expected_type: &str) -> Result<Element> {
+    let root = XmlParser::new(src).document()?;
+    if root.name != "structure" {
+        return Err(format!("expected <structure> as the root element but found <{}>",
+                           root.name));
+    }
+
+    let structure_type = root.child_text("type")?;
+    if structure_type != expected_type {
+        return Err(format!("expected a JFLAP structure of type {:?} but found {:?}",
+                           expected_type,
+                           structure_type));
+    }
+
+    Ok(root)
+}
+
+fn single_char(s: &str, context: &str) -> Result<char> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() != 1 {
+        return Err(format!("{} {:?} must be a single character", context, s));
+    }
+
+    Ok(chars[0])
+}
+
+fn structure(structure_type: &str, body: &str) -> String {
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?><!--Created with \
+             syntaxis.--><structure>\n\t<type>{}</type>\n{}</structure>\n",
+            structure_type,
+            body)
+}
+
+
+/// Reads a JFLAP finite automata. States are named after their name
+/// attribute and empty reads become 'λ' transitions.
+pub fn read_fa(src: &str) -> Result<M> {
+    let root = parse_structure(src, "fa")?;
+    let automaton = root.child("automaton").unwrap_or(&root);
+
+    let mut names: BTreeMap<String, State> = BTreeMap::new();
+    let mut k: StateSet = stateset!();
+    let mut f: StateSet = stateset!();
+    let mut q0: Option<State> = None;
+
+    for state in automaton.children_named("state") {
+        let id = match state.attrs.get("id") {
+            Some(id) => id.clone(),
+            None => return Err("<state> is missing the id attribute".to_string()),
+        };
+        let name = state.attrs.get("name").cloned().unwrap_or(id.clone());
+
+        if k.contains(&name) {
+            return Err(format!("duplicated state name {:?}", name));
+        }
+
+        if state.child("initial").is_some() {
+            if let Some(ref q0) = q0 {
+                return Err(format!("more than one initial state: {:?} and {:?}", q0, name));
+            }
+            q0 = Some(name.clone());
+        }
+
+        if state.child("final").is_some() {
+            f.insert(name.clone());
+        }
+
+        k.insert(name.clone());
+        names.insert(id, name);
+    }
+
+    let mut alphabet: Alphabet = alphabet!();
+    let mut delta: Delta = delta!();
+    for transition in automaton.children_named("transition") {
+        let from = transition.child_text("from")?;
+        let to = transition.child_text("to")?;
+        let s = names.get(&from).ok_or(format!("transition from unknown state {:?}", from))?;
+        let ns = names.get(&to).ok_or(format!("transition to unknown state {:?}", to))?;
+
+        let read = match transition.child("read") {
+            Some(read) => read.text.clone(),
+            None => String::new(),
+        };
+
+        let c = if read.is_empty() {
+            'λ'
+        } else {
+            let c = single_char(&read, "Transition read")?;
+            if c != 'λ' {
+                alphabet.insert(c);
+            }
+            c
+        };
+
+        delta.insert((s.clone(), c, ns.clone()));
+    }
+
+    let q0 = q0.ok_or("the automata has no initial state".to_string())?;
+    M::check(&k, &alphabet, &q0, &f, &delta)?;
+
+    Ok(M::new(k, alphabet, q0, f, delta))
+}
+
+/// Writes M as a JFLAP finite automata, 'λ' transitions become empty reads.
+/// Symbols of the alphabet that are not used by any transition are lost
+/// since JFLAP does not store the alphabet.
+pub fn write_fa(m: &M) -> String {
+    let ids: BTreeMap<&State, usize> = m.k.iter().enumerate().map(|(i, s)| (s, i)).collect();
+
+    let mut body = String::new();
+    body.push_str("\t<automaton>\n");
+    for (s, id) in &ids {
+        body.push_str(&format!("\t\t<state id=\"{}\" name=\"{}\">\n", id, escape(s)));
+        if **s == m.q0 {
+            body.push_str("\t\t\t<initial/>\n");
+        }
+        if m.f.contains(*s) {
+            body.push_str("\t\t\t<final/>\n");
+        }
+        body.push_str("\t\t</state>\n");
+    }
+
+    for (s, a, ns) in to_delta(m) {
+        body.push_str("\t\t<transition>\n");
+        body.push_str(&format!("\t\t\t<from>{}</from>\n", ids[&s]));
+        body.push_str(&format!("\t\t\t<to>{}</to>\n", ids[&ns]));
+        if a == 'λ' {
+            body.push_str("\t\t\t<read/>\n");
+        } else {
+            body.push_str(&format!("\t\t\t<read>{}</read>\n", escape(&a.to_string())));
+        }
+        body.push_str("\t\t</transition>\n");
+    }
+    body.push_str("\t</automaton>\n");
+
+    structure("fa", &body)
+}
+
+
+fn read_productions(src: &str) -> Result<Vec<(NonTerminal, String)>> {
+    let root = parse_structure(src, "grammar")?;
+
+    let mut productions = vec![];
+    for production in root.children_named("production") {
+        let left = production.child_text("left")?;
+        let left = single_char(&left, "Production left side")?;
+        if !left.is_uppercase() {
+            return Err(format!("Production left side {:?} must be an upper case variable",
+                               left));
+        }
+
+        let right = match production.child("right") {
+            Some(right) => right.text.trim().to_string(),
+            None => String::new(),
+        };
+        let right = if right == "λ" { String::new() } else { right };
+
+        productions.push((left, right));
+    }
+
+    if productions.is_empty() {
+        return Err("the grammar has no productions".to_string());
+    }
+
+    Ok(productions)
+}
+
+fn grammar_symbols(productions: &[(NonTerminal, String)]) -> (NonTerminalSet, TerminalSet) {
+    let mut vn: NonTerminalSet = charset!();
+    let mut vt: TerminalSet = charset!();
+    for &(left, ref right) in productions {
+        vn.insert(left);
+        for c in right.chars() {
+            if c.is_uppercase() {
+                vn.insert(c);
+            } else {
+                vt.insert(c);
+            }
+        }
+    }
+
+    (vn, vt)
+}
+
+fn write_productions(start: &str, productions: &[(String, String)]) -> String {
+    // JFLAP takes the left side of the first production as the start variable
+    let (start_productions, rest): (Vec<_>, Vec<_>) =
+        productions.iter().partition(|&(left, _)| left == start);
+
+    let mut body = String::new();
+    for &(left, right) in start_productions.iter().chain(rest.iter()) {
+        body.push_str("\t<production>\n");
+        body.push_str(&format!("\t\t<left>{}</left>\n", escape(left)));
+        if right.is_empty() {
+            body.push_str("\t\t<right/>\n");
+        } else {
+            body.push_str(&format!("\t\t<right>{}</right>\n", escape(right)));
+        }
+        body.push_str("\t</production>\n");
+    }
+
+    structure("grammar", &body)
+}
+
+/// Reads a JFLAP right linear grammar whose productions are all
+/// of the form A -> aB or A -> a.
+pub fn read_gr(src: &str) -> Result<GR> {
+    let productions = read_productions(src)?;
+    let (vn, vt) = grammar_symbols(&productions);
+    let q0 = productions[0].0;
+
+    for &(left, ref right) in &productions {
+        let chain: Vec<char> = right.chars().collect();
+        let regular = match chain.len() {
+            1 => vt.contains(&chain[0]),
+            2 => vt.contains(&chain[0]) && vn.contains(&chain[1]),
+            _ => false,
+        };
+
+        if !regular {
+            return Err(format!("NOT A REGULAR GRAMMAR. Error in production {} -> {:?}",
+                               left,
+                               right));
+        }
+    }
+
+    let productions: RegularProductions = productions.into_iter()
+        .map(|(left, right)| (left, right.chars().collect()))
+        .collect();
+
+    Ok(GR::new(vt, vn, productions, q0))
+}
+
+pub fn write_gr(gr: &GR) -> String {
+    let productions: Vec<(String, String)> = gr.productions
+        .iter()
+        .map(|&(left, ref right)| (left.to_string(), right.iter().collect()))
+        .collect();
+    write_productions(&gr.q0.to_string(), &productions)
+}
+
+/// Reads a JFLAP context free grammar, empty right sides become Lambda.
+pub fn read_cfg(src: &str) -> Result<CFG> {
+    let productions = read_productions(src)?;
+    let (vn, vt) = grammar_symbols(&productions);
+    let s = productions[0].0;
+
+    Ok(CFG::new(vn, vt, productions, s))
+}
+
+/// Writes a context free grammar, JFLAP symbols are single chars so this
+/// fails when a symbol is longer or does not follow the upper case
+/// convention for variables.
+pub fn write_cfg(g: &CFG) -> Result<String> {
+    for nt in &g.vn {
+        let c = single_char(nt, "Variable")?;
+        if !c.is_uppercase() {
+            return Err(format!("Variable {:?} must be upper case", nt));
+        }
+    }
+
+    for t in &g.vt {
+        let c = single_char(t, "Terminal")?;
+        if c.is_uppercase() {
+            return Err(format!("Terminal {:?} can not be upper case", t));
+        }
+    }
+
+    let mut productions: Vec<(String, String)> = vec![];
+    for (nt, dervec) in &g.p {
+        for der in dervec {
+            productions.push((nt.clone(), derivation_to_string(der)));
+        }
+    }
+
+    Ok(write_productions(&g.s, &productions))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FA: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><!--Created with JFLAP 7.1.--><structure>&#13;
+	<type>fa</type>&#13;
+	<automaton>&#13;
+		<!--The list of states.-->&#13;
+		<state id="0" name="q0">&#13;
+			<x>60.0</x>&#13;
+			<y>89.0</y>&#13;
+			<initial/>&#13;
+		</state>&#13;
+		<state id="1" name="q1">&#13;
+			<x>187.0</x>&#13;
+			<y>91.0</y>&#13;
+			<final/>&#13;
+		</state>&#13;
+		<state id="2" name="q2">&#13;
+			<x>187.0</x>&#13;
+			<y>191.0</y>&#13;
+		</state>&#13;
+		<!--The list of transitions.-->&#13;
+		<transition>&#13;
+			<from>0</from>&#13;
+			<to>1</to>&#13;
+			<read>a</read>&#13;
+		</transition>&#13;
+		<transition>&#13;
+			<from>1</from>&#13;
+			<to>2</to>&#13;
+			<read/>&#13;
+		</transition>&#13;
+		<transition>&#13;
+			<from>2</from>&#13;
+			<to>1</to>&#13;
+			<read>&lt;</read>&#13;
+		</transition>&#13;
+	</automaton>&#13;
+</structure>"#;
+
+    static GRAMMAR: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><!--Created with JFLAP 7.1.--><structure>
+	<type>grammar</type>
+	<!--The list of productions.-->
+	<production>
+		<left>S</left>
+		<right>aA</right>
+	</production>
+	<production>
+		<left>A</left>
+		<right>b</right>
+	</production>
+	<production>
+		<left>A</left>
+		<right>aA</right>
+	</production>
+</structure>"#;
+
+    #[test]
+    fn read_fa_test() {
+        let m = read_fa(FA).unwrap();
+
+        let m_expected = M::new(stateset!("q0", "q1", "q2"),
+                                alphabet!('a', '<'),
+                                "q0".to_string(),
+                                stateset!("q1"),
+                                delta!(("q0", 'a', "q1"), ("q1", 'λ', "q2"), ("q2", '<', "q1")));
+
+        assert_eq!(m, m_expected);
+    }
+
+    #[test]
+    fn write_fa_test() {
+        let m = read_fa(FA).unwrap();
+        let jff = write_fa(&m);
+
+        assert!(jff.contains("<state id=\"0\" name=\"q0\">\n\t\t\t<initial/>"));
+        assert!(jff.contains("<read/>"));
+        assert!(jff.contains("<read>&lt;</read>"));
+        assert_eq!(read_fa(&jff).unwrap(), m);
+    }
+
+    #[test]
+    fn read_fa_errors_test() {
+        assert!(read_fa("<structure><type>grammar</type></structure>").is_err());
+        assert!(read_fa("<structure><type>fa</type><automaton>").is_err());
+        assert!(read_fa("<structure><type>fa</type><automaton><state id=\"0\"/>\
+                         </automaton></structure>")
+                        .is_err());
+        assert!(read_fa("<structure><type>fa</type><automaton><state id=\"0\"><initial/>\
+                         </state><transition><from>0</from><to>0</to><read>ab</read>\
+                         </transition></automaton></structure>")
+                        .is_err());
+    }
+
+    #[test]
+    fn read_gr_test() {
+        use grammar::gr_to_afndl;
+        use automata_operators::afndl_to_afd;
+
+        let gr = read_gr(GRAMMAR).unwrap();
+        assert_eq!(gr.q0, 'S');
+        assert_eq!(gr.vn, charset!('S', 'A'));
+        assert_eq!(gr.vt, charset!('a', 'b'));
+
+        let mut m = afndl_to_afd(&gr_to_afndl(&gr));
+        assert!(m.check_string("ab").is_ok());
+        assert!(m.check_string("aaab").is_ok());
+        assert!(m.check_string("b").is_err());
+
+        let gr_back = read_gr(&write_gr(&gr)).unwrap();
+        assert_eq!(gr_back.productions, gr.productions);
+        assert_eq!(gr_back.q0, gr.q0);
+    }
+
+    #[test]
+    fn read_cfg_test() {
+        let jff = "<structure><type>grammar</type>\
+                   <production><left>S</left><right>(S)S</right></production>\
+                   <production><left>S</left><right/></production>\
+                   </structure>";
+
+        let g = read_cfg(jff).unwrap();
+        assert_eq!(g.s, "S");
+        assert_eq!(g.vt, stateset!("(", ")"));
+        assert_eq!(g.p["S"].len(), 2);
+
+        let jff = write_cfg(&g).unwrap();
+        assert!(jff.contains("<right>(S)S</right>"));
+        assert!(jff.contains("<right/>"));
+
+        let g_back = read_cfg(&jff).unwrap();
+        assert_eq!(g_back.p["S"].len(), 2);
+        assert!(read_gr(&jff).is_err());
+    }
+
+    #[test]
+    fn write_cfg_errors_test() {
+        use cfg_text::parse_grammar;
+
+        assert!(write_cfg(&parse_grammar("S -> \"id\" S | Lambda").unwrap()).is_err());
+        assert!(write_cfg(&parse_grammar("Expr -> \"a\"").unwrap()).is_err());
+        assert!(write_cfg(&parse_grammar("S -> \"A\"").unwrap()).is_err());
+        assert!(write_cfg(&parse_grammar("S -> \"a\" S | Lambda").unwrap()).is_ok());
+    }
+}
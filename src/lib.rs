@@ -1,14 +1,53 @@
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+pub use self::symbol::*;
+pub use self::random::*;
 pub use self::automata::*;
 pub use self::automata_operators::*;
 pub use self::automata_min::*;
+pub use self::automata_text::*;
 pub use self::grammar::*;
 pub use self::cfg::*;
+pub use self::cfg_sets::*;
+pub use self::cfg_ll1::*;
+pub use self::cfg_transform::*;
+pub use self::cfg_cyk::*;
+pub use self::cfg_earley::*;
+pub use self::cfg_lr::*;
+pub use self::cfg_lr1::*;
+pub use self::cfg_lr_parser::*;
+pub use self::cfg_precedence::*;
+pub use self::cfg_ambiguity::*;
+pub use self::cfg_derivation::*;
+pub use self::cfg_text::*;
 
 #[macro_use]
 mod macros;
+mod symbol;
+mod random;
 mod automata;
 mod automata_operators;
 mod automata_min;
+mod automata_text;
+mod automata_language;
 mod grammar;
 mod cfg;
+mod cfg_sets;
+mod cfg_ll1;
+mod cfg_transform;
+mod cfg_normal;
+mod cfg_cyk;
+mod cfg_earley;
+mod cfg_lr;
+mod cfg_lr1;
+mod cfg_lr_parser;
+mod cfg_precedence;
+mod cfg_ambiguity;
+mod cfg_derivation;
+mod cfg_generate;
+mod cfg_text;
+mod dot;
+pub mod jflap;
 pub mod regexp;
@@ -91,16 +91,16 @@ macro_rules! r_productions {
     () => {
         {
             use std::collections::{BTreeSet};
-            let aux: BTreeSet<(char, String)> = BTreeSet::new();
+            let aux: BTreeSet<(char, Vec<char>)> = BTreeSet::new();
             aux
         }
     };
     ( $( ($vn:expr, $chain:expr) ),* ) => {
         {
             use std::collections::{BTreeSet};
-            let mut aux: BTreeSet<(char, String)> = BTreeSet::new();
+            let mut aux: BTreeSet<(char, Vec<char>)> = BTreeSet::new();
             $(
-                aux.insert( ($vn, $chain.to_string()) );
+                aux.insert( ($vn, $chain.chars().collect()) );
             )*
             aux
         }
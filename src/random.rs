@@ -0,0 +1,96 @@
+// Small seedable pseudo random generator for the samplers of grammars and
+// automatas, so that generated test inputs can be reproduced from a seed
+// without depending on an external crate. It is SplitMix64 and is not
+// suitable for anything security related.
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+    }
+
+    /// Uniform number in 0..n, n must not be 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        // Rejects the last incomplete range of n values to avoid bias
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Uniform number in 0..n for ranges wider than u64, n must not be 0.
+    pub fn below_u128(&mut self, n: u128) -> u128 {
+        let zone = u128::MAX - u128::MAX % n;
+        loop {
+            let x = (u128::from(self.next_u64()) << 64) | u128::from(self.next_u64());
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Index chosen with probability proportional to its weight,
+    /// None when every weight is 0.
+    pub fn weighted(&mut self, weights: &[u64]) -> Option<usize> {
+        // Summed in u128 so large weights can't overflow
+        let total: u128 = weights.iter().map(|&w| u128::from(w)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut x = self.below_u128(total);
+        weights.iter().position(|&w| {
+            if x < u128::from(w) {
+                return true;
+            }
+            x -= u128::from(w);
+            false
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn rng_test() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert!(Rng::new(8).next_u64() != xs[0]);
+
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[a.below(3) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 900 && c < 1100), "{:?}", counts);
+
+        for _ in 0..100 {
+            assert_eq!(a.weighted(&[0, 5, 0]), Some(1));
+        }
+        assert_eq!(a.weighted(&[0, 0]), None);
+        assert!((0..100).all(|_| a.weighted(&[u64::MAX, 2]).is_some()));
+        assert!((0..100).all(|_| a.below_u128(1 << 100) < 1 << 100));
+    }
+}
@@ -1,5 +1,6 @@
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Token {
     pub category: String,
     pub lexeme: String,
@@ -1,8 +1,11 @@
 use std::collections::{HashSet, HashMap};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::OnceLock;
 
 use regexp::{Token, Node, NodeCat, lex, tree_to_automata};
+use cfg::{CFG, TNT};
+use cfg_text::parse_grammar;
 
 
 
@@ -33,47 +36,54 @@ impl Production {
     }
 }
 
-fn get_productions() -> Vec<Production> {
-    let prods = vec![Production::new("Re", vec!["Lit", "Ops"]),
-                     Production::new("Re", vec!["(", "Re", ")", "Ops"]),
+// Same grammar as grammar.md
+const GRAMMAR: &str = "Re -> \"Lit\" Ops | \"(\" Re \")\" Ops ;
+                               Ops -> \"*\" ReL | \"+\" ReL | \"|\" Re | Re | Lambda ;
+                               ReL -> Re | Lambda ;";
 
-                     Production::new("Ops", vec!["*", "ReL"]),
-                     Production::new("Ops", vec!["+", "ReL"]),
-                     Production::new("Ops", vec!["|", "Re"]),
-                     Production::new("Ops", vec!["Re"]),
-                     Production::new("Ops", vec!["Lambda"]),
+fn get_grammar() -> CFG {
+    parse_grammar(GRAMMAR).expect("Invalid regexp grammar")
+}
 
-                     Production::new("ReL", vec!["Re"]),
-                     Production::new("ReL", vec!["Lambda"])];
+fn symbol_name(e: &TNT) -> String {
+    match *e {
+        TNT::T(ref s) |
+        TNT::NT(ref s) => s.clone(),
+        TNT::Lambda => "Lambda".to_string(),
+        TNT::Eof => "EOF".to_string(),
+    }
+}
 
-    prods
+fn get_productions(g: &CFG) -> Vec<Production> {
+    g.productions()
+        .iter()
+        .map(|(nt, der)| Production::new(nt.clone(), der.iter().map(symbol_name).collect()))
+        .collect()
 }
 
-fn get_table() -> HashMap<(String, String), usize> {
-    let entries = [(("Re", "Lit"), 0),
-                   (("Re", "("), 1),
-
-                   (("Ops", "*"), 2),
-                   (("Ops", "+"), 3),
-                   (("Ops", "|"), 4),
-                   (("Ops", "Lit"), 5),
-                   (("Ops", "("), 5),
-                   (("Ops", "EOF"), 6),
-                   (("Ops", ")"), 6),
-                   (("Ops", "Lambda"), 6),
-
-                   (("ReL", "Lit"), 7),
-                   (("ReL", "("), 7),
-                   (("ReL", "EOF"), 8),
-                   (("ReL", ")"), 8),
-                   (("ReL", "Lambda"), 8)];
-
-    let mut table = HashMap::new();
-    for &((non_terminal, token_cat), value) in entries.iter() {
-        table.insert((non_terminal.to_string(), token_cat.to_string()), value);
-    }
+fn get_table(g: &CFG) -> HashMap<(String, String), usize> {
+    let table = g.ll1_table().expect("The regexp grammar is not LL(1)");
 
-    table
+    table.iter()
+        .map(|((nt, terminal), &index)| ((nt.clone(), symbol_name(terminal)), index))
+        .collect()
+}
+
+struct Tables {
+    productions: Vec<Production>,
+    table: HashMap<(String, String), usize>,
+}
+
+// The grammar is fixed, so its table is built by the first parser only
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+                           let grammar = get_grammar();
+                           Tables {
+                               productions: get_productions(&grammar),
+                               table: get_table(&grammar),
+                           }
+                       })
 }
 
 fn is_terminal(s: &String) -> bool {
@@ -89,8 +99,8 @@ struct Parser {
     pub index: usize,
     pub src: String,
     pub tokens: Vec<Token>,
-    pub productions: Vec<Production>,
-    pub table: HashMap<(String, String), usize>,
+    pub productions: &'static [Production],
+    pub table: &'static HashMap<(String, String), usize>,
     pub tree: Rc<RefCell<Node>>,
     stack: Vec<Rc<RefCell<Node>>>,
 }
@@ -100,6 +110,7 @@ impl Parser {
     pub fn new(src: String) -> Parser {
         let tokens = lex(src.clone());
         let root = Node::new_nt("Re");
+        let tables = tables();
 
         let stack = vec![Node::new_t(Token {
                                          category: "EOF".to_string(),
@@ -111,8 +122,8 @@ impl Parser {
             index: 0,
             src: src,
             tokens: tokens,
-            productions: get_productions(),
-            table: get_table(),
+            productions: &tables.productions,
+            table: &tables.table,
             stack: stack,
             tree: root.clone(),
         }
@@ -134,14 +145,6 @@ impl Parser {
             };
 
 
-            println!("pfocus {} {:?} stack {:?}",
-                     parse_focus.borrow().as_string(),
-                     token,
-                     self.stack
-                         .iter()
-                         .map(|s| s.borrow().as_string())
-                         .collect::<Vec<String>>());
-
             if cat == "EOF".to_string() && token.category == "EOF" {
                 return true;
             } else if is_terminal(&cat) {
@@ -151,7 +154,6 @@ impl Parser {
                     node.category = NodeCat::T(token.clone());
                     self.index += 1;
                 } else {
-                    println!("ERROR: wrong symbol at the top of the stack");
                     return false;
                 }
             } else {
@@ -163,8 +165,6 @@ impl Parser {
                     .get(*prod_number)
                     .expect("ERROR: expanding parse_focus 2");
 
-                println!("REDUCE {:?}", prod);
-
                 let root = self.stack.pop().unwrap();
                 let mut root = root.borrow_mut();
                 for s in &prod.to {
@@ -201,6 +201,48 @@ impl Parser {
 mod tests {
     use super::*;
 
+    #[test]
+    fn grammar_md_test() {
+        let md = include_str!("../../grammar.md");
+        let start = md.find("```\n").unwrap() + 4;
+        let end = start + md[start..].find("```").unwrap();
+
+        assert_eq!(get_grammar().p, parse_grammar(&md[start..end]).unwrap().p);
+    }
+
+    #[test]
+    fn get_table_test() {
+        let g = get_grammar();
+        let table = get_table(&g);
+        let productions = get_productions(&g);
+        let cell = |nt: &str, t: &str| {
+            let p = &productions[table[&(nt.to_string(), t.to_string())]];
+            assert_eq!(p.from, nt);
+            p.to.join(" ")
+        };
+
+        assert_eq!(table.len(), 13);
+        assert_eq!(cell("Re", "Lit"), "Lit Ops");
+        assert_eq!(cell("Re", "("), "( Re ) Ops");
+        assert_eq!(cell("Ops", "*"), "* ReL");
+        assert_eq!(cell("Ops", "+"), "+ ReL");
+        assert_eq!(cell("Ops", "|"), "| Re");
+        assert_eq!(cell("Ops", "Lit"), "Re");
+        assert_eq!(cell("Ops", "("), "Re");
+        assert_eq!(cell("Ops", "EOF"), "Lambda");
+        assert_eq!(cell("Ops", ")"), "Lambda");
+        assert_eq!(cell("ReL", "Lit"), "Re");
+        assert_eq!(cell("ReL", "("), "Re");
+        assert_eq!(cell("ReL", "EOF"), "Lambda");
+        assert_eq!(cell("ReL", ")"), "Lambda");
+
+        // Every parser shares the same table
+        let a = Parser::new("a".to_string());
+        let b = Parser::new("b*".to_string());
+        assert!(::std::ptr::eq(a.table, b.table));
+        assert_eq!(a.table, &table);
+    }
+
     #[test]
     fn parse_test() {
 
@@ -4,11 +4,13 @@ use std::rc::Rc;
 use automata::{M, print_automata};
 use automata_min::{minify, pretify_automata};
 use automata_operators::afndl_to_afd;
+use dot;
 
 use regexp::{Token, re_trivial, automata_intersection, automata_union, automata_star};
 
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NodeCat {
     T(Token),
     NT(String),
@@ -25,6 +27,7 @@ impl NodeCat {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Node {
     pub category: NodeCat,
     pub children: Vec<Rc<RefCell<Node>>>,
@@ -87,6 +90,41 @@ impl Node {
             Node::preorder_walk(c.clone(), level + 1);
         }
     }
+
+    /// Graphviz DOT representation of the tree rooted at x.
+    /// Terminals (tokens) are drawn as grey boxes and nonterminals as ellipses.
+    pub fn to_dot(x: Rc<RefCell<Node>>) -> String {
+        let mut body = String::new();
+        let mut next_id = 0;
+        Node::dot_walk(x, &mut next_id, &mut body);
+
+        dot::digraph("regexp", &body)
+    }
+
+    fn dot_walk(x: Rc<RefCell<Node>>, next_id: &mut usize, body: &mut String) -> usize {
+        let x = x.borrow();
+        let id = *next_id;
+        *next_id += 1;
+
+        match x.category {
+            NodeCat::NT(ref cat) => body.push_str(&dot::non_terminal_node(id, cat)),
+            NodeCat::T(ref token) => {
+                let label = if token.lexeme.is_empty() {
+                    token.category.clone()
+                } else {
+                    format!("{} '{}'", token.category, token.lexeme)
+                };
+                body.push_str(&dot::terminal_node(id, &label));
+            }
+        }
+
+        for c in &x.children {
+            let child_id = Node::dot_walk(c.clone(), next_id, body);
+            body.push_str(&dot::edge(id, child_id));
+        }
+
+        id
+    }
 }
 
 pub fn tree_to_automata(x: Rc<RefCell<Node>>) -> Option<M> {
@@ -196,6 +234,25 @@ pub fn tree_to_automata(x: Rc<RefCell<Node>>) -> Option<M> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn to_dot_test() {
+        use regexp::recursive_parser::Parser;
+
+        let p = Parser::new("a*".to_string());
+        p.parse();
+        let dot = Node::to_dot(p.tree.clone());
+
+        assert!(dot.starts_with("digraph regexp {"));
+        assert!(dot.contains("n0 [label=\"Re\", shape=ellipse];"));
+        assert!(dot.contains("n1 [label=\"Lit 'a'\", shape=box"));
+        assert!(dot.contains("n2 [label=\"Ops\", shape=ellipse];"));
+        assert!(dot.contains("n3 [label=\"*\", shape=box"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("n0 -> n2;"));
+        assert!(dot.contains("n2 -> n3;"));
+    }
+
     #[test]
     fn engine_test() {
         use regexp::recursive_parser::Parser;
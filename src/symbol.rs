@@ -0,0 +1,40 @@
+use std::fmt::{Debug, Display};
+
+
+/// Symbols that automatas and regular grammars can read.
+/// Plain chars are the default but token categories such as
+/// "ident" or "==" can be used as symbols by means of String.
+pub trait Symbol: Ord + Clone + Debug + Display {
+    /// The symbol used to represent lambda (empty string) transitions
+    fn lambda() -> Self;
+
+    fn is_lambda(&self) -> bool {
+        *self == Self::lambda()
+    }
+}
+
+impl Symbol for char {
+    fn lambda() -> char {
+        'λ'
+    }
+}
+
+impl Symbol for String {
+    fn lambda() -> String {
+        "λ".to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn lambda_test() {
+        assert!('λ'.is_lambda());
+        assert!(!'a'.is_lambda());
+        assert!("λ".to_string().is_lambda());
+        assert!(!"ident".to_string().is_lambda());
+    }
+}
@@ -0,0 +1,38 @@
+extern crate syntaxis;
+
+use syntaxis::{M, parse_automata, automata_to_text, afndl_to_afd, minify};
+
+fn fixture(src: &str) -> M {
+    parse_automata(src).unwrap_or_else(|e| panic!("Invalid fixture {}", e))
+}
+
+#[test]
+fn afndl_to_afd_fixture() {
+    let afndl = fixture(include_str!("fixtures/afndl.txt"));
+    let afd_expected = fixture(include_str!("fixtures/afd.txt"));
+
+    let afd = afndl_to_afd(&afndl);
+    assert_eq!(afd, afd_expected);
+}
+
+#[test]
+fn minify_fixture() {
+    let m = fixture(include_str!("fixtures/afd_not_min.txt"));
+    let min_expected = fixture(include_str!("fixtures/afd_min.txt"));
+
+    let min_m = minify(&m);
+    assert_eq!(min_m, min_expected);
+}
+
+#[test]
+fn fixtures_round_trip() {
+    let fixtures = vec![include_str!("fixtures/afndl.txt"),
+                        include_str!("fixtures/afd.txt"),
+                        include_str!("fixtures/afd_not_min.txt"),
+                        include_str!("fixtures/afd_min.txt")];
+
+    for src in fixtures {
+        let m = fixture(src);
+        assert_eq!(fixture(&automata_to_text(&m)), m);
+    }
+}
@@ -0,0 +1,50 @@
+extern crate syntaxis;
+
+use syntaxis::{LL1Parser, parse_grammar};
+
+// A small configuration language
+//
+//     [server]
+//     port = 8080;
+//     name = "syntaxis";
+const CONFIG_GRAMMAR: &str = "
+    Config -> Entry Config | Lambda ;
+    Entry -> \"[\" \"ident\" \"]\" | \"ident\" \"=\" Value \";\" ;
+    Value -> \"string\" | \"number\" | \"bool\" ;
+";
+
+fn lex(src: &str) -> Vec<(String, String)> {
+    let spaced = src.replace('[', " [ ").replace(']', " ] ").replace('=', " = ").replace(';', " ; ");
+
+    spaced.split_whitespace()
+        .map(|lexeme| {
+            let category = match lexeme {
+                "[" | "]" | "=" | ";" => lexeme,
+                "true" | "false" => "bool",
+                _ if lexeme.starts_with('"') => "string",
+                _ if lexeme.chars().all(|c| c.is_ascii_digit()) => "number",
+                _ => "ident",
+            };
+            (category.to_string(), lexeme.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn config_dsl() {
+    let g = parse_grammar(CONFIG_GRAMMAR).unwrap();
+    let parser = LL1Parser::from_grammar(&g).unwrap();
+
+    let tokens = lex("[server]\nport = 8080;\nname = \"syntaxis\";\ndebug = true;");
+    let categories: Vec<&str> = tokens.iter().map(|t| t.0.as_str()).collect();
+    let tree = parser.parse(&categories).unwrap();
+
+    assert_eq!(tree.leaves(), categories);
+
+    let tokens = lex("port = ;");
+    let categories: Vec<&str> = tokens.iter().map(|t| t.0.as_str()).collect();
+    let err = parser.parse(&categories).unwrap_err();
+    assert_eq!(err.position, 2);
+    assert_eq!(err.to_string(),
+               "token 2: expected bool, number, string but found ;");
+}
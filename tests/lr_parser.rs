@@ -0,0 +1,117 @@
+extern crate syntaxis;
+
+use std::collections::BTreeMap;
+
+use syntaxis::{LRParser, TNT, parse_grammar};
+
+// The configuration language of ll1_parser.rs written left recursive,
+// which an LR parser takes as is
+const CONFIG_GRAMMAR: &str = "
+    Config -> Config Entry | Lambda ;
+    Entry -> \"[\" \"ident\" \"]\" | \"ident\" \"=\" Value \";\" ;
+    Value -> \"string\" | \"number\" | \"bool\" ;
+";
+
+fn lex(src: &str) -> Vec<(String, String)> {
+    let spaced = src.replace('[', " [ ")
+        .replace(']', " ] ")
+        .replace('=', " = ")
+        .replace(';', " ; ");
+
+    spaced.split_whitespace()
+        .map(|lexeme| {
+            let category = match lexeme {
+                "[" | "]" | "=" | ";" => lexeme,
+                "true" | "false" => "bool",
+                _ if lexeme.starts_with('"') => "string",
+                _ if lexeme.chars().all(|c| c.is_ascii_digit()) => "number",
+                _ => "ident",
+            };
+            (category.to_string(), lexeme.to_string())
+        })
+        .collect()
+}
+
+// Semantic values of the translation to a map of section.key to value,
+// Entry(None) stands for input skipped by error recovery
+#[derive(Debug, Clone)]
+enum Value {
+    Lexeme(String),
+    Entry(Option<(String, String)>),
+    Section(String),
+    Config(String, BTreeMap<String, String>),
+}
+
+fn translate(parser: &LRParser, tokens: &[(String, String)]) -> BTreeMap<String, String> {
+    let categories: Vec<&str> = tokens.iter().map(|t| t.0.as_str()).collect();
+    let lexeme = |value: &Value| match *value {
+        Value::Lexeme(ref lexeme) => lexeme.clone(),
+        _ => unreachable!(),
+    };
+
+    let sync = vec![TNT::T(";".to_string())].into_iter().collect();
+    let shift = |position: usize, _: &str| Value::Lexeme(tokens[position].1.clone());
+    let reduce = |production: usize, mut values: Vec<Value>| {
+        match parser.productions()[production].0.as_str() {
+            "Config" if values.is_empty() => Value::Config(String::new(), BTreeMap::new()),
+            "Config" => {
+                let entry = values.pop().unwrap();
+                let (mut section, mut map) = match values.pop().unwrap() {
+                    Value::Config(section, map) => (section, map),
+                    _ => unreachable!(),
+                };
+                match entry {
+                    Value::Section(name) => section = name,
+                    Value::Entry(Some((key, value))) => {
+                        map.insert(format!("{}.{}", section, key), value);
+                    }
+                    _ => {}
+                }
+                Value::Config(section, map)
+            }
+            "Entry" if values.len() == 3 => Value::Section(lexeme(&values[1])),
+            "Entry" => {
+                match values[2] {
+                    Value::Lexeme(ref value) => {
+                        Value::Entry(Some((lexeme(&values[0]), value.clone())))
+                    }
+                    _ => Value::Entry(None),
+                }
+            }
+            _ => values.pop().unwrap(),
+        }
+    };
+
+    let config = parser.parse_recovering_with(&categories, &sync, shift, reduce, |_| {
+        Value::Entry(None)
+    });
+
+    match config.value {
+        Some(Value::Config(_, map)) => map,
+        _ => panic!("{:?}", config.errors),
+    }
+}
+
+#[test]
+fn config_dsl_translation() {
+    let g = parse_grammar(CONFIG_GRAMMAR).unwrap();
+    let parser = LRParser::from_grammar(&g).unwrap();
+
+    let src = "[server]\nport = 8080;\nname = \"syntaxis\";\n[log]\ndebug = true;";
+    let map = translate(&parser, &lex(src));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["server.port"], "8080");
+    assert_eq!(map["server.name"], "\"syntaxis\"");
+    assert_eq!(map["log.debug"], "true");
+
+    // The broken entry is skipped up to its ; and the rest is kept
+    let tokens = lex("[server]\nport = = 8080;\nname = \"syntaxis\";");
+    let categories: Vec<&str> = tokens.iter().map(|t| t.0.as_str()).collect();
+    let err = parser.parse(&categories).unwrap_err();
+    assert_eq!(err.to_string(),
+               "token 5: expected bool, number, string but found =");
+
+    let map = translate(&parser, &tokens);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map["server.name"], "\"syntaxis\"");
+}
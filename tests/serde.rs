@@ -0,0 +1,120 @@
+#![cfg(feature = "serde")]
+
+#[macro_use]
+extern crate syntaxis;
+extern crate serde_json;
+
+use syntaxis::{M, GR, CFG};
+use syntaxis::regexp::{Token, Node};
+
+#[test]
+fn automata_json() {
+    let m = M::new(stateset!("q0", "q1"),
+                   alphabet!('a'),
+                   "q0".to_string(),
+                   stateset!("q1"),
+                   delta!(("q0", 'a', "q1"), ("q1", 'λ', "q0")));
+
+    let json = serde_json::to_string(&m).unwrap();
+    let expected = r#"{"k":["q0","q1"],"alphabet":["a"],"q0":"q0","f":["q1"],"delta":[["q0","a","q1"],["q1","λ","q0"]]}"#;
+    assert_eq!(json, expected);
+
+    let m_back: M = serde_json::from_str(&json).unwrap();
+    assert_eq!(m_back, m);
+}
+
+#[test]
+fn automata_json_invalid() {
+    let json = r#"{"k":["q0"],"alphabet":["a"],"q0":"q0","f":["q0"],"delta":[["q0","b","q0"]]}"#;
+    let m: Result<M, _> = serde_json::from_str(json);
+    assert!(m.is_err());
+
+    let json = r#"{"k":["q0"],"alphabet":["a"],"q0":"q1","f":[],"delta":[]}"#;
+    let m: Result<M, _> = serde_json::from_str(json);
+    assert!(m.is_err());
+}
+
+#[test]
+fn gr_json() {
+    let gr = GR::new(charset!('a'),
+                     charset!('S'),
+                     r_productions!(('S', "aS"), ('S', "a")),
+                     'S');
+
+    let json = serde_json::to_string(&gr).unwrap();
+    let expected = r#"{"vt":["a"],"vn":["S"],"productions":[["S",["a"]],["S",["a","S"]]],"q0":"S"}"#;
+    assert_eq!(json, expected);
+
+    let gr_back: GR = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&gr_back).unwrap(), expected);
+}
+
+#[test]
+fn gr_json_invalid() {
+    let json = r#"{"vt":["a"],"vn":["S"],"productions":[["S",["b","S"]]],"q0":"S"}"#;
+    let gr: Result<GR, _> = serde_json::from_str(json);
+    assert!(gr.is_err());
+
+    let json = r#"{"vt":["a"],"vn":["S"],"productions":[],"q0":"A"}"#;
+    let gr: Result<GR, _> = serde_json::from_str(json);
+    assert!(gr.is_err());
+
+    let json = r#"{"vt":["a","S"],"vn":["S"],"productions":[],"q0":"S"}"#;
+    let gr: Result<GR, _> = serde_json::from_str(json);
+    assert!(gr.is_err());
+}
+
+#[test]
+fn cfg_json() {
+    let g = CFG::new(charset!('S'),
+                     charset!('a', '(', ')'),
+                     vec![('S', "(S)"), ('S', "a"), ('S', "")],
+                     'S');
+
+    let json = serde_json::to_string(&g).unwrap();
+    let expected = r#"{"vn":["S"],"vt":["(",")","a"],"p":{"S":[[{"T":"("},{"NT":"S"},{"T":")"}],[{"T":"a"}],["Lambda"]]},"s":"S"}"#;
+    assert_eq!(json, expected);
+
+    let g_back: CFG = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&g_back).unwrap(), expected);
+}
+
+#[test]
+fn cfg_json_multi_char() {
+    let json = r#"{"vn":["Expr"],"vt":["id","+"],"p":{"Expr":[[{"NT":"Expr"},{"T":"+"},{"T":"id"}],[{"T":"id"}]]},"s":"Expr"}"#;
+    let g: CFG = serde_json::from_str(json).unwrap();
+    assert_eq!(g.p["Expr"].len(), 2);
+}
+
+#[test]
+fn cfg_json_invalid() {
+    let json = r#"{"vn":["S"],"vt":["a"],"p":{"S":[[{"T":"b"}]]},"s":"S"}"#;
+    let g: Result<CFG, _> = serde_json::from_str(json);
+    assert!(g.is_err());
+
+    let json = r#"{"vn":["S"],"vt":["S"],"p":{},"s":"S"}"#;
+    let g: Result<CFG, _> = serde_json::from_str(json);
+    assert!(g.is_err());
+}
+
+#[test]
+fn regexp_json() {
+    let token = Token {
+        category: "Lit".to_string(),
+        lexeme: "a".to_string(),
+    };
+    let json = serde_json::to_string(&token).unwrap();
+    assert_eq!(json, r#"{"category":"Lit","lexeme":"a"}"#);
+
+    let tree = Node::new_nt("Re");
+    tree.borrow_mut().children.push(Node::new_t(token));
+
+    let json = serde_json::to_string(&tree).unwrap();
+    let expected = r#"{"category":{"NT":"Re"},"children":[{"category":{"T":{"category":"Lit","lexeme":"a"}},"children":[]}]}"#;
+    assert_eq!(json, expected);
+
+    let tree_back: Node = serde_json::from_str(&json).unwrap();
+    assert_eq!(tree_back.children.len(), 1);
+    assert_eq!(tree_back.category.as_string(), "Re");
+    assert_eq!(tree_back.children[0].borrow().category.as_string(), "Lit");
+}